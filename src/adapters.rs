@@ -0,0 +1,129 @@
+use super::args::ExtArgs;
+use super::process_impl::{by_value, idents, replace_self};
+use super::routing::item_cfgs;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::{FnArg, GenericParam, ImplItemFn, ItemImpl, Pat};
+
+fn error(tokens: impl ToTokens, message: &str) -> syn::Error {
+    syn::Error::new_spanned(tokens, format!("`adapter`: {}", message))
+}
+
+/// The struct for `#[ext(adapter = Name)]` on the method `m`, which holds the receiver (as `iter`)
+/// and the other arguments of the method
+///
+/// The method still constructs it itself and the `Iterator` impl is left to the user, so only
+/// the boilerplate around them is generated. The struct's generic parameters are the method's
+/// lifetimes that the arguments use, then the receiver and then the method's other generic
+/// parameters that the arguments use.
+pub fn adapter(
+    args: &ExtArgs,
+    trait_name: &Ident,
+    item: &ItemImpl,
+    m: &ImplItemFn,
+    name: &Ident,
+) -> syn::Result<TokenStream2> {
+    if !matches!(m.sig.receiver(), Some(r) if by_value(r)) {
+        return Err(error(&m.sig, "the method has to take `self`"));
+    }
+    let impl_params: Vec<String> = item
+        .generics
+        .type_params()
+        .map(|p| p.ident.to_string())
+        .filter(|p| item.self_ty.to_token_stream().to_string() != *p)
+        .collect();
+    let method_params: Vec<&GenericParam> = m.sig.generics.params.iter().collect();
+    let receiver = ["I", "Iter", "Inner"]
+        .iter()
+        .map(|n| Ident::new(n, Span::call_site()))
+        .find(|n| !m.sig.generics.type_params().any(|p| p.ident == *n))
+        .ok_or_else(|| {
+            error(
+                &m.sig.generics,
+                "the method uses all names for the receiver",
+            )
+        })?;
+
+    let mut fields = Vec::new();
+    let mut used = std::collections::HashSet::new();
+    for arg in m.sig.inputs.iter().skip(1) {
+        let t = match arg {
+            FnArg::Typed(t) => t,
+            FnArg::Receiver(_) => continue,
+        };
+        let field = match &*t.pat {
+            Pat::Ident(p) if p.subpat.is_none() => &p.ident,
+            pat => return Err(error(pat, "arguments have to be plain names")),
+        };
+        let mut ty = (*t.ty).clone();
+        replace_self(&mut ty, &syn::parse_quote!(#receiver));
+        let spelled = ty.to_token_stream().to_string();
+        let mentioned = idents(&ty);
+        if mentioned.contains("impl") {
+            return Err(error(&t.ty, "arguments can't have `impl Trait` types"));
+        }
+        // the struct doesn't have the bounds that associated types would need
+        if mentioned.contains("Self") {
+            return Err(error(
+                &t.ty,
+                "arguments can't use associated types of `Self`",
+            ));
+        }
+        if let Some(p) = impl_params.iter().find(|p| mentioned.contains(*p)) {
+            return Err(error(
+                &t.ty,
+                &format!("arguments can't use the impl's parameter `{}`", p),
+            ));
+        }
+        for p in &method_params {
+            let used_here = match p {
+                GenericParam::Lifetime(l) => spelled.contains(&l.lifetime.to_string()),
+                GenericParam::Type(t) => mentioned.contains(&t.ident.to_string()),
+                GenericParam::Const(c) => mentioned.contains(&c.ident.to_string()),
+            };
+            if used_here {
+                used.insert(p.to_token_stream().to_string());
+            }
+        }
+        fields.push(quote!(#field: #ty));
+    }
+
+    // only the names of the parameters, since the struct doesn't have any bounds
+    let is_used = |p: &&&GenericParam| used.contains(&p.to_token_stream().to_string());
+    let lifetimes = method_params
+        .iter()
+        .filter(is_used)
+        .filter_map(|p| match p {
+            GenericParam::Lifetime(l) => Some(&l.lifetime),
+            _ => None,
+        });
+    let params = method_params
+        .iter()
+        .filter(is_used)
+        .filter_map(|p| match p {
+            GenericParam::Type(t) => Some(t.ident.to_token_stream()),
+            GenericParam::Const(c) => {
+                let (ident, ty) = (&c.ident, &c.ty);
+                Some(quote!(const #ident: #ty))
+            }
+            GenericParam::Lifetime(_) => None,
+        });
+
+    let vis = args.vis();
+    let cfg = args.cfg_attrs();
+    let item_cfg = item_cfgs(&m.attrs);
+    let doc = format!(
+        " The iterator adapter returned by [`{}::{}`]",
+        trait_name, m.sig.ident
+    );
+    Ok(quote! {
+        #(#cfg)*
+        #(#item_cfg)*
+        #[doc = #doc]
+        #[must_use = "iterator adapters are lazy and do nothing unless consumed"]
+        #vis struct #name<#(#lifetimes,)* #receiver, #(#params),*> {
+            iter: #receiver,
+            #(#fields,)*
+        }
+    })
+}
@@ -0,0 +1,934 @@
+use super::forward::{Forward, Lift};
+use super::naming::NameStyle;
+use super::routing::ImplOnly;
+use super::test_doubles::DummyImpls;
+use super::tokens_eq;
+use super::tuples::Arities;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::format_ident;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Expr, ImplItem, ItemImpl, LitStr, Path, Token, Type, TypeParam, Visibility};
+
+/// A single `key`, `key = value`, `key(...)` or `key(...) { ... }` entry in the argument list
+pub struct RawArg {
+    pub key: Ident,
+    pub value: RawValue,
+}
+
+pub enum RawValue {
+    Flag,
+    Assign(TokenStream2),
+    List(TokenStream2),
+    /// The contents of the parentheses and of the braces
+    Block(TokenStream2, TokenStream2),
+}
+
+impl RawArg {
+    fn is_start(input: ParseStream) -> bool {
+        matches!(
+            input.fork().call(Ident::parse_any),
+            Ok(key) if KNOWN_OPTIONS.contains(&key.to_string().as_str())
+        )
+    }
+
+    pub fn expect_flag(&self) -> syn::Result<()> {
+        match self.value {
+            RawValue::Flag => Ok(()),
+            _ => Err(syn::Error::new(
+                self.key.span(),
+                format!("`{}` doesn't take a value", self.key),
+            )),
+        }
+    }
+
+    pub fn parse_assign<T: Parse>(&self) -> syn::Result<T> {
+        self.parse_assign_with(T::parse)
+    }
+
+    pub fn parse_assign_with<P: Parser>(&self, parser: P) -> syn::Result<P::Output> {
+        match &self.value {
+            RawValue::Assign(ts) => parser.parse2(ts.clone()),
+            _ => Err(syn::Error::new(
+                self.key.span(),
+                format!("expected `{} = ...`", self.key),
+            )),
+        }
+    }
+
+    pub fn parse_args_with<P: Parser>(&self, parser: P) -> syn::Result<P::Output> {
+        match &self.value {
+            RawValue::List(ts) => parser.parse2(ts.clone()),
+            _ => Err(syn::Error::new(
+                self.key.span(),
+                format!("expected `{}(...)`", self.key),
+            )),
+        }
+    }
+
+    /// The arguments and items of `key(...) { ... }`
+    pub fn parse_block<T: Parse>(&self) -> syn::Result<(T, Vec<ImplItem>)> {
+        let parse_items = |input: ParseStream| {
+            let mut items = Vec::new();
+            while !input.is_empty() {
+                items.push(input.parse()?);
+            }
+            Ok(items)
+        };
+        match &self.value {
+            RawValue::Block(args, items) => Ok((
+                syn::parse2(args.clone())?,
+                parse_items.parse2(items.clone())?,
+            )),
+            _ => Err(syn::Error::new(
+                self.key.span(),
+                format!("expected `{}(...) {{ ... }}`", self.key),
+            )),
+        }
+    }
+
+    pub fn parse_args<T: Parse>(&self) -> syn::Result<T> {
+        self.parse_args_with(T::parse)
+    }
+
+    pub fn parse_list<T: Parse>(&self) -> syn::Result<Vec<T>> {
+        self.parse_args_with(Punctuated::<T, Token![,]>::parse_terminated)
+            .map(|p| p.into_iter().collect())
+    }
+}
+
+impl Parse for RawArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.call(Ident::parse_any)?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            let mut ts = TokenStream2::new();
+            while !input.is_empty() && !input.peek(Token![,]) {
+                ts.extend(Some(input.parse::<proc_macro2::TokenTree>()?));
+            }
+            RawValue::Assign(ts)
+        } else if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            if input.peek(syn::token::Brace) {
+                let items;
+                syn::braced!(items in input);
+                RawValue::Block(content.parse()?, items.parse()?)
+            } else {
+                RawValue::List(content.parse()?)
+            }
+        } else {
+            RawValue::Flag
+        };
+        Ok(RawArg { key, value })
+    }
+}
+
+/// Option keys take precedence over trait names (which are expected to be CamelCase anyway)
+const KNOWN_OPTIONS: &[&str] = &[
+    "alias",
+    "allow",
+    "allow_missing_docs",
+    "also_fns",
+    "also_for",
+    "also_inherent",
+    "append_to",
+    "assert_impl_for",
+    "cfg",
+    "debug_dump",
+    "decl_module",
+    "defaults",
+    "delegatable",
+    "deprecated",
+    "const",
+    "copy_attrs",
+    "coverage_off",
+    "doc_cfg",
+    "doc_links",
+    "docs_on_impl",
+    "doctest_example",
+    "downcast",
+    "dummy_impl",
+    "dyn_clone",
+    "faux",
+    "for_floats",
+    "for_primitives",
+    "forward",
+    "gen_mock",
+    "gen_spy",
+    "hidden_module",
+    "id",
+    "impl_only",
+    "inline_all",
+    "lift",
+    "mixed_site",
+    "mock",
+    "msrv",
+    "name",
+    "newtype",
+    "no_auto_sized",
+    "no_automatically_derived",
+    "notable",
+    "per_method_bounds",
+    "prelude",
+    "pub_if",
+    "require_docs",
+    "sealed",
+    "self_in_defaults",
+    "slim_impl",
+    "sorted",
+    "split_consuming",
+    "split_mut",
+    "split_self_predicates",
+    "tuples",
+    "type_defaults",
+    "type_name",
+    "usage",
+    "vis",
+    "warn_dead_code",
+    "warn_shadowing",
+];
+
+/// The options that [`ExtArgs::inherit`] passes on from `#[ext_mod]` to its blocks
+const INHERITED_OPTIONS: &[&str] = &[
+    "allow",
+    "allow_missing_docs",
+    "cfg",
+    "const",
+    "copy_attrs",
+    "coverage_off",
+    "debug_dump",
+    "delegatable",
+    "deprecated",
+    "doc_cfg",
+    "doc_links",
+    "docs_on_impl",
+    "downcast",
+    "dyn_clone",
+    "faux",
+    "hidden_module",
+    "impl_only",
+    "inline_all",
+    "mixed_site",
+    "mock",
+    "msrv",
+    "name",
+    "no_auto_sized",
+    "no_automatically_derived",
+    "notable",
+    "per_method_bounds",
+    "prelude",
+    "pub_if",
+    "require_docs",
+    "sealed",
+    "self_in_defaults",
+    "slim_impl",
+    "sorted",
+    "split_consuming",
+    "split_mut",
+    "split_self_predicates",
+    "type_defaults",
+    "type_name",
+    "usage",
+    "vis",
+    "warn_dead_code",
+    "warn_shadowing",
+];
+
+fn primitive_family(family: &Ident) -> syn::Result<&'static [&'static str]> {
+    Ok(match family.to_string().as_str() {
+        "signed" => &["i8", "i16", "i32", "i64", "i128", "isize"],
+        "unsigned" => &["u8", "u16", "u32", "u64", "u128", "usize"],
+        "floats" => &["f32", "f64"],
+        _ => {
+            return Err(syn::Error::new(
+                family.span(),
+                "expected one of `signed`, `unsigned` or `floats`",
+            ))
+        }
+    })
+}
+
+/// What `doc_links` links
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DocLinks {
+    /// The trait links to the extended type
+    Target,
+    /// Additionally, the methods of the impl link back to the trait
+    SeeAlso,
+}
+
+/// The oldest Rust version that the generated code has to compile with, e.g. `msrv = "1.65"`
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Msrv {
+    pub minor: u32,
+}
+
+impl Msrv {
+    /// Generic associated types
+    pub const GATS: Msrv = Msrv { minor: 65 };
+    /// `async fn` and `-> impl Trait` in traits
+    pub const TRAIT_ASYNC_AND_IMPL: Msrv = Msrv { minor: 75 };
+    /// `use<...>` bounds of `impl Trait` types in traits
+    pub const TRAIT_PRECISE_CAPTURES: Msrv = Msrv { minor: 87 };
+}
+
+impl std::fmt::Display for Msrv {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "1.{}", self.minor)
+    }
+}
+
+impl Parse for Msrv {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let version: LitStr = input.parse()?;
+        let value = version.value();
+        let mut parts = value.split('.');
+        match (parts.next(), parts.next().map(str::parse), parts.next()) {
+            (Some("1"), Some(Ok(minor)), None) => Ok(Msrv { minor }),
+            (Some("1"), Some(Ok(minor)), Some(patch)) if patch.parse::<u32>().is_ok() => {
+                Ok(Msrv { minor })
+            }
+            _ => Err(syn::Error::new(
+                version.span(),
+                "expected a version like `\"1.65\"`",
+            )),
+        }
+    }
+}
+
+/// The arguments of `#[ext(...)]` (and of `#[ext_mod(...)]`)
+#[derive(Default)]
+pub struct ExtArgs {
+    /// The keys of the options that were given, in order
+    pub keys: Vec<Ident>,
+    pub vis: Option<Visibility>,
+    pub ident: Option<Ident>,
+    /// The (nested) module that the trait is emitted in, e.g. `traits` for `traits::BytesExt`
+    pub module: Vec<Ident>,
+    /// Define the trait in a hidden module of its own and re-export it
+    pub hidden_module: bool,
+    /// A naming template like `"{type}Ext"`
+    pub name_template: Option<LitStr>,
+    /// How `{type}` is spelled in the template
+    pub name_style: NameStyle,
+    /// Derive the auto-generated name from this instead of the input (see `ext_trait_name!`)
+    pub id: Option<LitStr>,
+    pub sealed: bool,
+    /// Add the items to the trait of an earlier block in the same `#[ext_mod]`
+    pub append_to: Option<Ident>,
+    /// The blocks after the first one in an `ext_block!`, which implement the same trait
+    pub other_blocks: Vec<ItemImpl>,
+    /// Also export a macro that implements the trait, for `#[ext_decl]`
+    pub decl: bool,
+    /// The path of the module from the crate root, for the macro of `#[ext_decl]`
+    pub decl_module: Option<Path>,
+    /// Re-export the trait under this name
+    pub alias: Option<(Visibility, Ident)>,
+    /// Register the trait for `ext_prelude!`
+    pub prelude: bool,
+    /// The `cfg` predicate under which the trait is `pub` instead of `pub(crate)`
+    pub pub_if: Option<TokenStream2>,
+    /// Without a visibility, use the one of the items (`vis = infer`)
+    pub infer_vis: bool,
+    /// The predicates of `cfg(...)` arguments
+    pub cfg: Vec<TokenStream2>,
+    /// The attribute that disables coverage instrumentation of generated glue code
+    pub coverage_off: Option<Attribute>,
+    /// The `#[deprecated]` attribute of the trait
+    pub deprecated: Option<Attribute>,
+    /// Allow `missing_docs` on the trait
+    pub allow_missing_docs: bool,
+    /// The lints of `allow(...)`, which are allowed on the trait and the impls
+    pub allow: Vec<Path>,
+    /// The oldest Rust version to generate code for
+    pub msrv: Option<Msrv>,
+    /// Make missing doc comments on the trait and its items an error
+    pub require_docs: bool,
+    /// Don't allow `dead_code` on traits that aren't `pub`
+    pub warn_dead_code: bool,
+    /// The module to import the trait from in the generated "Usage" docs
+    pub usage: Option<Path>,
+    /// The value that the doctest in the "Usage" section calls a method on
+    pub doctest_example: Option<Expr>,
+    /// Which intra-doc links to add
+    pub doc_links: Option<DocLinks>,
+    /// The flag (usually `docsrs`) under which `#[doc(cfg)]` attributes are emitted
+    pub doc_cfg: Option<Ident>,
+    /// The flag (usually `docsrs`) under which the trait is marked `#[doc(notable_trait)]`
+    pub notable: Option<Ident>,
+    /// Don't mark the generated impls as `#[automatically_derived]`
+    pub no_automatically_derived: bool,
+    /// Which attributes are copied to the trait
+    pub impl_only: ImplOnly,
+    /// Drop presentation-only attributes from the impl
+    pub slim_impl: bool,
+    /// Drop doc comments from the trait
+    pub docs_on_impl: bool,
+    /// Order the items of the trait by kind and name
+    pub sorted: bool,
+    /// Put the `&mut self` methods into their own trait
+    pub split_mut: bool,
+    /// Put the `self` methods into their own trait
+    pub split_consuming: bool,
+    /// Spell the self type as `Self` in the bodies of `#[ext(default)]` items
+    pub self_in_defaults: bool,
+    /// Only use `Self` in the trait's where clause and only the self type in the impl's
+    pub split_self_predicates: bool,
+    /// Put the bounds onto the methods of the trait instead of the trait itself
+    pub per_method_bounds: bool,
+    /// Don't add `where Self: Sized` to methods that need it
+    pub no_auto_sized: bool,
+    /// Make the trait a `#[const_trait]` that is implemented with `impl const`
+    pub const_trait: bool,
+    /// Make the associated types of the impl the defaults of those of the trait
+    pub type_defaults: bool,
+    /// Add `#[inline]` to all methods of the impls (but not the trait)
+    pub inline_all: bool,
+    /// Write the expansion to a file
+    pub debug_dump: bool,
+    /// Give the local variables of generated code `Span::mixed_site()` hygiene
+    pub mixed_site: bool,
+    /// Warn about methods named like std trait methods
+    pub warn_shadowing: bool,
+    /// Derive a `mockall` mock of the trait in test builds
+    pub mock: bool,
+    /// Mark the impl for `faux`
+    pub faux: bool,
+    /// Make the trait object cloneable with `dyn_clone`
+    pub dyn_clone: bool,
+    /// Make the trait object downcastable with `downcast_rs`
+    pub downcast: bool,
+    /// Types to implement the trait for with bodies that don't do anything
+    pub dummy_impl: Option<DummyImpls>,
+    /// The name of the dependency-free mock struct to generate in test builds
+    pub gen_mock: Option<Ident>,
+    /// The name of the recording wrapper struct to generate in test builds
+    pub gen_spy: Option<Ident>,
+    /// Defaults for the type parameters of the trait, like `T = u8`
+    pub defaults: Vec<TypeParam>,
+    /// Additional self types to implement the trait for
+    pub also_for: Vec<Type>,
+    /// The name of the wrapper struct to generate
+    pub newtype: Option<Ident>,
+    /// Generate the macro that `#[ext_delegate]` calls
+    pub delegatable: bool,
+    /// Also implement the trait for `Option<Type>` and/or `Result<Type, E>`
+    pub lift: Vec<Lift>,
+    /// Also implement the trait for smart pointers to the self type, like `Cow<'_, Type>`
+    pub forward: Vec<Forward>,
+    /// Keep the inherent impl and forward to it
+    pub also_inherent: bool,
+    /// Generate free functions that call the methods, in the given module (if any)
+    pub also_fns: Option<Option<Ident>>,
+    /// Types that have to implement the trait
+    pub assert_impl_for: Vec<Type>,
+    /// Implement the trait for tuples of these arities instead of the placeholder `Tuple`
+    pub tuples: Option<Arities>,
+}
+
+impl ExtArgs {
+    fn apply(&mut self, arg: RawArg) -> syn::Result<()> {
+        self.keys.push(arg.key.clone());
+        match arg.key.to_string().as_str() {
+            "name" => self.name_template = Some(arg.parse_assign()?),
+            "type_name" => self.name_style = arg.parse_args()?,
+            "id" => self.id = Some(arg.parse_assign()?),
+            "hidden_module" => {
+                arg.expect_flag()?;
+                self.hidden_module = true;
+            }
+            "sealed" => {
+                arg.expect_flag()?;
+                self.sealed = true;
+            }
+            "cfg" => self.cfg.push(arg.parse_args()?),
+            "pub_if" => {
+                if let Some(vis) = &self.vis {
+                    return Err(syn::Error::new_spanned(
+                        vis,
+                        "`pub_if` can't be combined with a visibility",
+                    ));
+                }
+                self.pub_if = Some(arg.parse_args()?);
+            }
+            "vis" => {
+                let value: Ident = arg.parse_assign()?;
+                if value != "infer" {
+                    return Err(syn::Error::new(value.span(), "expected `vis = infer`"));
+                }
+                if let Some(vis) = &self.vis {
+                    return Err(syn::Error::new_spanned(
+                        vis,
+                        "`vis = infer` can't be combined with a visibility",
+                    ));
+                }
+                self.infer_vis = true;
+            }
+            "deprecated" => {
+                self.deprecated = Some(match arg.value {
+                    RawValue::Flag => syn::parse_quote!(#[deprecated]),
+                    _ => {
+                        let note: LitStr = arg.parse_assign()?;
+                        syn::parse_quote!(#[deprecated = #note])
+                    }
+                })
+            }
+            "coverage_off" => {
+                // the flag has to be named, since it has to be declared with `check-cfg` in every
+                // crate that uses it
+                let options: Vec<Ident> = arg.parse_list()?;
+                let attr = match &options[..] {
+                    [flag] => syn::parse_quote!(#[cfg_attr(#flag, coverage(off))]),
+                    [flag, old] if old == "no_coverage" => {
+                        syn::parse_quote!(#[cfg_attr(#flag, no_coverage)])
+                    }
+                    _ => {
+                        let message =
+                            "expected `coverage_off(flag)` or `coverage_off(flag, no_coverage)`";
+                        return Err(syn::Error::new(arg.key.span(), message));
+                    }
+                };
+                self.coverage_off = Some(attr);
+            }
+            "doc_cfg" => {
+                self.doc_cfg = Some(match arg.value {
+                    RawValue::Flag => Ident::new("docsrs", Span::call_site()),
+                    _ => arg.parse_assign()?,
+                })
+            }
+            "doc_links" => {
+                self.doc_links = Some(match arg.value {
+                    RawValue::Flag => DocLinks::Target,
+                    _ => {
+                        for flag in arg.parse_list::<Ident>()? {
+                            if flag != "see_also" {
+                                return Err(syn::Error::new(flag.span(), "expected `see_also`"));
+                            }
+                        }
+                        DocLinks::SeeAlso
+                    }
+                })
+            }
+            "notable" => {
+                self.notable = Some(match arg.value {
+                    RawValue::Flag => Ident::new("docsrs", Span::call_site()),
+                    _ => arg.parse_assign()?,
+                })
+            }
+            "allow_missing_docs" => {
+                arg.expect_flag()?;
+                self.allow_missing_docs = true;
+            }
+            "allow" => self.allow.extend(arg.parse_list()?),
+            "msrv" => self.msrv = Some(arg.parse_assign()?),
+            "require_docs" => {
+                arg.expect_flag()?;
+                self.require_docs = true;
+            }
+            "warn_dead_code" => {
+                arg.expect_flag()?;
+                self.warn_dead_code = true;
+            }
+            "usage" => self.usage = Some(arg.parse_assign_with(Path::parse_mod_style)?),
+            "doctest_example" => self.doctest_example = Some(arg.parse_assign()?),
+            "no_automatically_derived" => {
+                arg.expect_flag()?;
+                self.no_automatically_derived = true;
+            }
+            "copy_attrs" => self.impl_only.copied.extend(arg.parse_list::<Path>()?),
+            "impl_only" => {
+                for path in arg.parse_list()? {
+                    self.impl_only.add(path);
+                }
+            }
+            "split_consuming" => {
+                arg.expect_flag()?;
+                self.split_consuming = true;
+            }
+            "split_mut" => {
+                arg.expect_flag()?;
+                self.split_mut = true;
+            }
+            "sorted" => {
+                arg.expect_flag()?;
+                self.sorted = true;
+            }
+            "docs_on_impl" => {
+                arg.expect_flag()?;
+                self.docs_on_impl = true;
+            }
+            "slim_impl" => {
+                arg.expect_flag()?;
+                self.slim_impl = true;
+            }
+            "split_self_predicates" => {
+                arg.expect_flag()?;
+                self.split_self_predicates = true;
+            }
+            "self_in_defaults" => {
+                arg.expect_flag()?;
+                self.self_in_defaults = true;
+            }
+            "per_method_bounds" => {
+                arg.expect_flag()?;
+                self.per_method_bounds = true;
+            }
+            "no_auto_sized" => {
+                arg.expect_flag()?;
+                self.no_auto_sized = true;
+            }
+            "const" => {
+                arg.expect_flag()?;
+                if !cfg!(feature = "const_trait") {
+                    return Err(syn::Error::new(
+                        arg.key.span(),
+                        "`const` needs the `const_trait` feature of ext_trait",
+                    ));
+                }
+                self.const_trait = true;
+            }
+            "type_defaults" => {
+                arg.expect_flag()?;
+                if !cfg!(feature = "associated_type_defaults") {
+                    return Err(syn::Error::new(
+                        arg.key.span(),
+                        "`type_defaults` needs the `associated_type_defaults` feature of ext_trait",
+                    ));
+                }
+                self.type_defaults = true;
+            }
+            "inline_all" => {
+                arg.expect_flag()?;
+                self.inline_all = true;
+                // an `#[inline]` that's already there mustn't end up on the trait either
+                self.impl_only.add(syn::parse_quote!(inline));
+            }
+            "mixed_site" => {
+                arg.expect_flag()?;
+                self.mixed_site = true;
+            }
+            "debug_dump" => {
+                arg.expect_flag()?;
+                self.debug_dump = true;
+            }
+            "warn_shadowing" => {
+                arg.expect_flag()?;
+                self.warn_shadowing = true;
+            }
+            "dummy_impl" => self.dummy_impl = Some(arg.parse_args()?),
+            "gen_mock" => self.gen_mock = Some(arg.parse_assign()?),
+            "gen_spy" => self.gen_spy = Some(arg.parse_assign()?),
+            "downcast" => {
+                arg.expect_flag()?;
+                self.downcast = true;
+            }
+            "dyn_clone" => {
+                arg.expect_flag()?;
+                self.dyn_clone = true;
+            }
+            "faux" => {
+                arg.expect_flag()?;
+                self.faux = true;
+            }
+            "mock" => {
+                arg.expect_flag()?;
+                self.mock = true;
+            }
+            "alias" => {
+                self.alias =
+                    Some(arg.parse_assign_with(|input: ParseStream| {
+                        Ok((input.parse()?, input.parse()?))
+                    })?)
+            }
+            "append_to" => self.append_to = Some(arg.parse_assign()?),
+            "decl_module" => self.decl_module = Some(arg.parse_assign()?),
+            "prelude" => {
+                arg.expect_flag()?;
+                self.prelude = true;
+            }
+            "also_for" => self.also_for.extend(arg.parse_list()?),
+            "newtype" => self.newtype = Some(arg.parse_assign()?),
+            "delegatable" => {
+                arg.expect_flag()?;
+                self.delegatable = true;
+            }
+            "lift" => {
+                for ident in arg.parse_list::<Ident>()? {
+                    self.lift.push(Lift::from_ident(&ident)?);
+                }
+            }
+            "forward" => {
+                for ident in arg.parse_list::<Ident>()? {
+                    self.forward.push(Forward::from_ident(&ident)?);
+                }
+            }
+            "also_fns" => {
+                self.also_fns = Some(match arg.value {
+                    RawValue::Flag => None,
+                    _ => Some(arg.parse_assign()?),
+                })
+            }
+            "also_inherent" => {
+                arg.expect_flag()?;
+                self.also_inherent = true;
+            }
+            "assert_impl_for" => self.assert_impl_for.extend(arg.parse_list()?),
+            "defaults" => self.defaults.extend(arg.parse_list()?),
+            "for_primitives" => {
+                for family in arg.parse_list::<Ident>()? {
+                    self.push_primitives(primitive_family(&family)?);
+                }
+            }
+            "tuples" => self.tuples = Some(arg.parse_args()?),
+            "for_floats" => {
+                arg.expect_flag()?;
+                self.push_primitives(&["f32", "f64"]);
+            }
+            _ => {
+                return Err(syn::Error::new(
+                    arg.key.span(),
+                    format!("unknown ext option `{}`", arg.key),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn push_primitives(&mut self, names: &[&str]) {
+        self.also_for.extend(names.iter().map(|name| {
+            let ident = Ident::new(name, Span::call_site());
+            syn::parse_quote!(#ident)
+        }));
+    }
+
+    /// Make sure that all options can be passed on by [`inherit`](Self::inherit), since the others
+    /// would be ignored, e.g. for `#[ext_mod(...)]`
+    pub fn check_inheritable(&self, what: &str) -> syn::Result<()> {
+        if let Some(ident) = &self.ident {
+            return Err(syn::Error::new(
+                ident.span(),
+                format!("{} can't name the trait, give the name to a block", what),
+            ));
+        }
+        match self
+            .keys
+            .iter()
+            .find(|key| !INHERITED_OPTIONS.contains(&key.to_string().as_str()))
+        {
+            Some(key) => Err(syn::Error::new(
+                key.span(),
+                format!(
+                    "`{}` isn't passed on to the blocks by {}, give it to a block",
+                    key, what
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Fill in everything that wasn't given explicitly from `defaults`
+    pub fn inherit(&mut self, defaults: &ExtArgs) {
+        if self.vis.is_none() && self.pub_if.is_none() {
+            self.vis = defaults.vis.clone();
+            self.pub_if = defaults.pub_if.clone();
+        }
+        self.infer_vis |= defaults.infer_vis;
+        if self.ident.is_none() && self.name_template.is_none() {
+            self.name_template = defaults.name_template.clone();
+            self.name_style = defaults.name_style;
+        }
+        self.hidden_module |= defaults.hidden_module;
+        self.sealed |= defaults.sealed;
+        self.prelude |= defaults.prelude;
+        self.mock |= defaults.mock;
+        self.faux |= defaults.faux;
+        self.dyn_clone |= defaults.dyn_clone;
+        self.downcast |= defaults.downcast;
+        self.delegatable |= defaults.delegatable;
+        self.slim_impl |= defaults.slim_impl;
+        self.docs_on_impl |= defaults.docs_on_impl;
+        self.sorted |= defaults.sorted;
+        self.split_mut |= defaults.split_mut;
+        self.split_consuming |= defaults.split_consuming;
+        self.inline_all |= defaults.inline_all;
+        self.const_trait |= defaults.const_trait;
+        self.type_defaults |= defaults.type_defaults;
+        self.no_auto_sized |= defaults.no_auto_sized;
+        self.per_method_bounds |= defaults.per_method_bounds;
+        self.allow_missing_docs |= defaults.allow_missing_docs;
+        self.allow.extend(defaults.allow.iter().cloned());
+        self.msrv = self.msrv.or(defaults.msrv);
+        self.require_docs |= defaults.require_docs;
+        self.warn_dead_code |= defaults.warn_dead_code;
+        self.split_self_predicates |= defaults.split_self_predicates;
+        self.self_in_defaults |= defaults.self_in_defaults;
+        self.debug_dump |= defaults.debug_dump;
+        self.mixed_site |= defaults.mixed_site;
+        self.warn_shadowing |= defaults.warn_shadowing;
+        self.impl_only.extend(&defaults.impl_only);
+        self.no_automatically_derived |= defaults.no_automatically_derived;
+        self.cfg.extend(defaults.cfg.iter().cloned());
+        if self.deprecated.is_none() {
+            self.deprecated = defaults.deprecated.clone();
+        }
+        if self.coverage_off.is_none() {
+            self.coverage_off = defaults.coverage_off.clone();
+        }
+        if self.usage.is_none() {
+            self.usage = defaults.usage.clone();
+        }
+        if self.doc_cfg.is_none() {
+            self.doc_cfg = defaults.doc_cfg.clone();
+        }
+        if self.doc_links.is_none() {
+            self.doc_links = defaults.doc_links;
+        }
+        if self.notable.is_none() {
+            self.notable = defaults.notable.clone();
+        }
+    }
+
+    pub fn cfg_attrs(&self) -> Vec<Attribute> {
+        self.cfg
+            .iter()
+            .map(|predicate| syn::parse_quote!(#[cfg(#predicate)]))
+            .collect()
+    }
+
+    /// The attributes for code that is generated without being written by the user
+    pub fn glue_attrs(&self) -> Vec<Attribute> {
+        let mut attrs = self.cfg_attrs();
+        attrs.extend(self.coverage_off.clone());
+        attrs
+    }
+
+    /// The attributes of generated `use`s of the trait
+    pub fn reexport_attrs(&self) -> Vec<Attribute> {
+        let mut attrs = self.cfg_attrs();
+        if self.deprecated.is_some() {
+            attrs.push(syn::parse_quote!(#[allow(deprecated)]));
+        }
+        attrs
+    }
+
+    /// The module that the trait `name` is hidden in
+    pub fn hidden_module(&self, name: &Ident) -> Option<Ident> {
+        if self.hidden_module {
+            Some(format_ident!("__ext_{}", name))
+        } else {
+            None
+        }
+    }
+
+    /// The path to the trait `name` from where the impl is
+    pub fn trait_path(&self, name: &Ident) -> Path {
+        let module = &self.module;
+        syn::parse_quote!(#(#module::)* #name)
+    }
+
+    /// The span of the local variables that generated code introduces
+    pub fn span(&self) -> Span {
+        if self.mixed_site {
+            Span::mixed_site()
+        } else {
+            Span::call_site()
+        }
+    }
+
+    /// With `vis = infer` and without a visibility, use the one that all items of the impl are
+    /// written with (if there are any and they agree), since `pub` on the items of an inherent impl
+    /// means the same
+    pub fn infer_vis(&mut self, item: &ItemImpl) {
+        if !self.infer_vis || self.vis.is_some() || self.pub_if.is_some() {
+            return;
+        }
+        let mut visibilities = item.items.iter().filter_map(|ii| match ii {
+            ImplItem::Fn(m) => Some(&m.vis),
+            ImplItem::Const(c) => Some(&c.vis),
+            ImplItem::Type(t) => Some(&t.vis),
+            _ => None,
+        });
+        let first = match visibilities.next() {
+            Some(Visibility::Inherited) | None => return,
+            Some(vis) => vis,
+        };
+        if visibilities.all(|vis| tokens_eq(vis, first)) {
+            self.vis = Some(first.clone());
+        }
+    }
+
+    pub fn vis(&self) -> Visibility {
+        match &self.pub_if {
+            // everything inside of the item that depends on `pub_if` can be `pub`
+            Some(_) => syn::parse_quote!(pub),
+            None => self.vis.clone().unwrap_or(Visibility::Inherited),
+        }
+    }
+}
+
+impl Parse for ExtArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = ExtArgs::default();
+
+        let vis: Visibility = input.parse()?;
+        if !matches!(vis, Visibility::Inherited) {
+            args.vis = Some(vis);
+        }
+        if input.peek(syn::Ident) && !RawArg::is_start(input) {
+            // `traits::BytesExt` puts the trait into the module `traits`
+            let path = input.call(Path::parse_mod_style)?;
+            let mut idents: Vec<Ident> = path.segments.into_iter().map(|s| s.ident).collect();
+            args.ident = idents.pop();
+            args.module = idents;
+        }
+
+        let mut first = args.vis.is_none() && args.ident.is_none();
+        while !input.is_empty() {
+            if !first {
+                input.parse::<Token![,]>()?;
+                if input.is_empty() {
+                    break;
+                }
+            }
+            first = false;
+            args.apply(input.parse()?)?;
+        }
+
+        Ok(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExtArgs, INHERITED_OPTIONS, KNOWN_OPTIONS};
+
+    fn inheritable(s: &str) -> bool {
+        let args: ExtArgs = syn::parse_str(s).unwrap();
+        args.check_inheritable("`#[ext_mod]`").is_ok()
+    }
+
+    #[test]
+    fn inherited_options_are_known() {
+        for option in INHERITED_OPTIONS {
+            assert!(KNOWN_OPTIONS.contains(option), "{}", option);
+        }
+    }
+
+    #[test]
+    fn check_inheritable() {
+        assert!(inheritable(
+            "pub(crate), name = \"{type}Ext\", sealed, msrv = \"1.60\""
+        ));
+        for s in [
+            "Name",
+            "also_for(u16)",
+            "newtype = Wrapper",
+            "id = \"a\"",
+            "tuples(3)",
+        ] {
+            assert!(!inheritable(s), "{}", s);
+        }
+    }
+}
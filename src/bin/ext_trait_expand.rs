@@ -0,0 +1,120 @@
+//! Print what the `#[ext]` blocks in a Rust file expand to
+//!
+//! `ext_trait_expand path/to/file.rs` checks the package that the file belongs to (with all of its
+//! targets, modules and dependencies) with `EXT_TRAIT_DEBUG_DIR` set, and prints the dumps of the
+//! blocks in that file, in the order of their lines. The expansions depend on that variable, so
+//! they happen again even if the package was already checked.
+
+use std::collections::btree_map::{BTreeMap, Entry};
+use std::path::{Path, PathBuf};
+use std::process::{exit, Command};
+
+const USAGE: &str = "usage: ext_trait_expand <file.rs>";
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", message);
+    exit(1)
+}
+
+/// The directory of the nearest manifest with a `[package]` above the file
+fn package_dir(file: &Path) -> Option<&Path> {
+    file.ancestors().skip(1).find(|dir| {
+        std::fs::read_to_string(dir.join("Cargo.toml"))
+            .map(|manifest| manifest.lines().any(|l| l.trim() == "[package]"))
+            .unwrap_or(false)
+    })
+}
+
+/// Only the characters that are fine in a file name everywhere, the same way as the directories
+/// of the dumps are named
+fn file_name_safe(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// The dumps of the blocks in `file` (whose directory name is or ends with `file`), formatted with
+/// `rustfmt` if it's available, by line and name
+///
+/// The compiler names files relative to the workspace, which can include more than the path
+/// inside the package, hence the suffix.
+fn read_dumps(dir: &Path, file: &str) -> std::io::Result<BTreeMap<(usize, String), String>> {
+    let mut dumps = BTreeMap::new();
+    if !dir.exists() {
+        return Ok(dumps);
+    }
+    // a directory per crate, with a directory per file in it
+    for krate in std::fs::read_dir(dir)? {
+        for file_dir in std::fs::read_dir(krate?.path())? {
+            let file_dir = file_dir?.path();
+            let name = file_dir.file_name().map(|n| n.to_string_lossy());
+            let matches =
+                matches!(name, Some(n) if n == file || n.ends_with(&format!("_{}", file)));
+            if !matches {
+                continue;
+            }
+            for dump in std::fs::read_dir(&file_dir)? {
+                let path = dump?.path();
+                let stem = path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let (name, line) = match stem.rsplit_once('-') {
+                    Some((name, line)) => (name.to_string(), line.parse().unwrap_or(0)),
+                    None => (stem, 0),
+                };
+                // the unformatted dump is still better than nothing
+                let _ = Command::new("rustfmt").arg(&path).status();
+                // blocks that are in several crates (like the library and its tests) are the same
+                if let Entry::Vacant(e) = dumps.entry((line, name)) {
+                    e.insert(std::fs::read_to_string(&path)?);
+                }
+            }
+        }
+    }
+    Ok(dumps)
+}
+
+fn main() {
+    let mut args = std::env::args_os().skip(1);
+    let file = match (args.next(), args.next()) {
+        (Some(file), None) if file != "-h" && file != "--help" => PathBuf::from(file),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2)
+        }
+    };
+    let file = std::fs::canonicalize(&file)
+        .ok()
+        .filter(|f| f.is_file())
+        .unwrap_or_else(|| fail(format!("`{}` isn't a file", file.display())));
+    let package = package_dir(&file)
+        .unwrap_or_else(|| fail(format!("`{}` isn't part of a package", file.display())));
+    let relative = file.strip_prefix(package).unwrap_or(&file);
+
+    let dumps_dir = std::env::temp_dir().join(format!("ext_trait_expand-{}", std::process::id()));
+    let output = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+        .args(["check", "--quiet", "--all-targets", "--manifest-path"])
+        .arg(package.join("Cargo.toml"))
+        .env("EXT_TRAIT_DEBUG_DIR", &dumps_dir)
+        .output()
+        .unwrap_or_else(|e| fail(format!("couldn't run cargo: {}", e)));
+    let dumps = read_dumps(&dumps_dir, &file_name_safe(&relative.to_string_lossy()));
+    let _ = std::fs::remove_dir_all(&dumps_dir);
+
+    let dumps = dumps.unwrap_or_else(|e| fail(format!("couldn't read the expansions: {}", e)));
+    if dumps.is_empty() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        fail("nothing was expanded");
+    }
+    for (i, ((line, name), dump)) in dumps.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("// {} (line {})", name, line);
+        print!("{}", dump);
+    }
+}
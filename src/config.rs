@@ -0,0 +1,129 @@
+use super::args::ExtArgs;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::path::PathBuf;
+use syn::LitStr;
+
+/// The table of the manifest that holds the defaults
+const TABLE: &str = "[package.metadata.ext_trait]";
+
+/// The value of `defaults = ...` in the table, if there is one
+///
+/// This only understands single-line strings (basic or literal), which is all that is needed for
+/// a list of options, and reports anything else instead of guessing.
+fn find_defaults(manifest: &str) -> Result<Option<String>, String> {
+    let mut in_table = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_table = line == TABLE;
+            continue;
+        }
+        let value = match line.strip_prefix("defaults") {
+            Some(rest) if in_table => match rest.trim_start().strip_prefix('=') {
+                Some(value) => value.trim(),
+                None => continue,
+            },
+            _ => continue,
+        };
+        return parse_string(value).map(Some);
+    }
+    Ok(None)
+}
+
+fn parse_string(value: &str) -> Result<String, String> {
+    let unsupported = || format!("expected a single-line string, found `{}`", value);
+    let mut chars = value.chars();
+    let quote = match chars.next() {
+        Some(q @ ('"' | '\'')) if !value.starts_with("\"\"\"") && !value.starts_with("'''") => q,
+        _ => return Err(unsupported()),
+    };
+    let mut out = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c == quote => {
+                let rest = chars.as_str().trim();
+                return if rest.is_empty() || rest.starts_with('#') {
+                    Ok(out)
+                } else {
+                    Err(unsupported())
+                };
+            }
+            '\\' if quote == '"' => match chars.next() {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                _ => return Err(unsupported()),
+            },
+            c => out.push(c),
+        }
+    }
+    Err(unsupported())
+}
+
+/// Fill in the options that `args` doesn't set from the `defaults` in the manifest of the crate
+/// that is being compiled, the same way as `#[ext_mod]` does
+///
+/// The returned tokens make the compiler track the manifest, so that changing the defaults
+/// expands the blocks again (cargo doesn't rebuild a crate when only its metadata changes).
+pub fn apply(args: &mut ExtArgs) -> syn::Result<TokenStream2> {
+    let dir = match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => dir,
+        None => return Ok(TokenStream2::new()),
+    };
+    let path = PathBuf::from(dir).join("Cargo.toml");
+    let manifest = match std::fs::read_to_string(&path) {
+        Ok(manifest) => manifest,
+        Err(_) => return Ok(TokenStream2::new()),
+    };
+    let error = |message: String| {
+        syn::Error::new(
+            Span::call_site(),
+            format!(
+                "in `defaults` of `{}` in {}: {}",
+                TABLE,
+                path.display(),
+                message
+            ),
+        )
+    };
+    if let Some(defaults) = find_defaults(&manifest).map_err(error)? {
+        let defaults: ExtArgs = syn::parse_str(&defaults).map_err(|e| error(e.to_string()))?;
+        defaults
+            .check_inheritable("the defaults")
+            .map_err(|e| error(e.to_string()))?;
+        args.inherit(&defaults);
+    }
+    let path = LitStr::new(&path.to_string_lossy(), Span::call_site());
+    Ok(quote! {
+        const _: &[u8] = include_bytes!(#path);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_defaults;
+
+    #[test]
+    fn defaults() {
+        let manifest = |table: &str| format!("[package]\nname = \"a\"\n\n{}\n", table);
+        let found = |table: &str| find_defaults(&manifest(table));
+        assert_eq!(found(""), Ok(None));
+        assert_eq!(
+            found("[package.metadata.ext_trait]\ndefaults = 'pub(crate), name = \"{type}Ext\"'"),
+            Ok(Some("pub(crate), name = \"{type}Ext\"".to_string()))
+        );
+        assert_eq!(
+            found(
+                "[package.metadata.ext_trait]\ndefaults = \"sealed, name = \\\"X{type}\\\"\" # x"
+            ),
+            Ok(Some("sealed, name = \"X{type}\"".to_string()))
+        );
+        assert_eq!(
+            found("[package.metadata.other]\ndefaults = 'sealed'"),
+            Ok(None)
+        );
+        assert!(found("[package.metadata.ext_trait]\ndefaults = '''\nsealed'''").is_err());
+        assert!(found("[package.metadata.ext_trait]\ndefaults = ['sealed']").is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::path::{Path, PathBuf};
+use syn::ext::IdentExt;
+
+/// The environment variable that enables dumps of every expansion (and sets where they go)
+const DIR_VAR: &str = "EXT_TRAIT_DEBUG_DIR";
+
+/// Makes the compiler track `EXT_TRAIT_DEBUG_DIR`, so that setting it (or changing it) expands the
+/// blocks again, instead of cargo reusing the last build without writing any dumps
+pub fn tracking() -> TokenStream2 {
+    quote! {
+        const _: Option<&str> = option_env!(#DIR_VAR);
+    }
+}
+
+/// Only the characters that are fine in a file name everywhere
+fn file_name_safe(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Where in `dir` the expansion of the trait `name` (from the impl at `span`) goes
+///
+/// There is a directory per crate and source file, and the line of the impl is part of the name,
+/// so that traits with the same name in other crates, files or modules don't overwrite it.
+fn dump_path(dir: &Path, name: &Ident, span: Span) -> PathBuf {
+    let krate = std::env::var("CARGO_CRATE_NAME").unwrap_or_default();
+    dir.join(file_name_safe(&krate))
+        .join(file_name_safe(&super::relative_file(span)))
+        .join(format!("{}-{}.rs", name.unraw(), span.start().line))
+}
+
+/// Write the expansion to a file if that was requested with `debug_dump` or `EXT_TRAIT_DEBUG_DIR`
+///
+/// The file isn't formatted, since running `rustfmt` for every block would slow down the build.
+pub fn dump(flag: bool, name: &Ident, span: Span, expanded: &TokenStream2) -> syn::Result<()> {
+    let dir = match std::env::var_os(DIR_VAR) {
+        Some(dir) => PathBuf::from(dir),
+        None if flag => std::env::temp_dir().join("ext_trait"),
+        None => return Ok(()),
+    };
+    let path = dump_path(&dir, name, span);
+    let write = || -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, expanded.to_string())
+    };
+    write().map_err(|e| {
+        syn::Error::new(
+            Span::call_site(),
+            format!("couldn't write debug dump to `{}`: {}", path.display(), e),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dump_path;
+    use proc_macro2::TokenStream as TokenStream2;
+    use std::path::Path;
+
+    #[test]
+    fn paths() {
+        let tokens: TokenStream2 = "Name\nName".parse().unwrap();
+        let names: Vec<_> = tokens
+            .into_iter()
+            .map(|tt| match tt {
+                proc_macro2::TokenTree::Ident(i) => i,
+                tt => panic!("{}", tt),
+            })
+            .collect();
+        let dir = Path::new("dumps");
+        let first = dump_path(dir, &names[0], names[0].span());
+        let second = dump_path(dir, &names[1], names[1].span());
+        assert_ne!(first, second);
+        assert!(first.starts_with(dir));
+        assert_eq!(first.extension(), Some("rs".as_ref()));
+        assert!(first
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("Name-"));
+    }
+}
@@ -0,0 +1,296 @@
+use super::args::ExtArgs;
+use super::item_args::impl_item_ident;
+use super::naming;
+use super::process_impl::by_value;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{FnArg, ImplItem, ItemImpl, ItemTrait, LitInt, Signature, Token, TraitItem, Visibility};
+
+/// What `__ext_impl!` checks of a method besides its name: the kind of receiver and the number of
+/// the other arguments
+#[derive(PartialEq)]
+struct Shape {
+    receiver: &'static str,
+    args: usize,
+}
+
+impl Shape {
+    fn of(sig: &Signature) -> Self {
+        let receiver = match sig.receiver() {
+            None => "no",
+            Some(r) if by_value(r) => "self",
+            Some(r) => match &r.reference {
+                Some(_) if r.mutability.is_some() => "mut",
+                Some(_) => "ref",
+                None => "typed",
+            },
+        };
+        let args = sig
+            .inputs
+            .iter()
+            .filter(|a| matches!(a, FnArg::Typed(_)))
+            .count();
+        Shape { receiver, args }
+    }
+
+    fn describe(&self) -> String {
+        let receiver = match self.receiver {
+            "no" => "no receiver",
+            "self" => "`self`",
+            "ref" => "`&self`",
+            "mut" => "`&mut self`",
+            _ => "a typed `self`",
+        };
+        format!("{} and {} other argument(s)", receiver, self.args)
+    }
+}
+
+impl ToTokens for Shape {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let receiver = Ident::new(self.receiver, Span::call_site());
+        let args = LitInt::new(&self.args.to_string(), Span::call_site());
+        tokens.extend(quote!((#receiver #args)));
+    }
+}
+
+/// An item of the trait, with the shape if it's a method
+struct DeclItem {
+    ident: Ident,
+    shape: Option<Shape>,
+}
+
+impl ToTokens for DeclItem {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        self.ident.to_tokens(tokens);
+        self.shape.to_tokens(tokens);
+    }
+}
+
+impl Parse for DeclItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.call(Ident::parse_any)?;
+        let shape = if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            let receiver = content.call(Ident::parse_any)?;
+            let receiver = ["no", "self", "ref", "mut", "typed"]
+                .iter()
+                .find(|r| receiver == r)
+                .ok_or_else(|| syn::Error::new(receiver.span(), "unknown receiver"))?;
+            let args: LitInt = content.parse()?;
+            Some(Shape {
+                receiver,
+                args: args.base10_parse()?,
+            })
+        } else {
+            None
+        };
+        Ok(DeclItem { ident, shape })
+    }
+}
+
+/// The exported macro of `#[ext_decl]`, along with a hidden re-export of `__ext_impl!` that it
+/// calls, so that crates using the macro don't need to depend on this one
+///
+/// The macro passes the path of the trait and the names of its required and optional items on to
+/// `__ext_impl!`, which checks the impl against them. Methods also come with their [`Shape`].
+pub fn capsule(args: &ExtArgs, trait_def: &ItemTrait) -> syn::Result<TokenStream2> {
+    if !trait_def.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &trait_def.generics,
+            "`ext_decl` doesn't support generic traits",
+        ));
+    }
+    if !matches!(args.vis(), Visibility::Public(_)) {
+        return Err(syn::Error::new(
+            trait_def.ident.span(),
+            "`ext_decl` needs a `pub` trait",
+        ));
+    }
+
+    let mut required = Vec::new();
+    let mut optional = Vec::new();
+    for ti in &trait_def.items {
+        let (ident, shape, has_default) = match ti {
+            TraitItem::Const(c) => (&c.ident, None, c.default.is_some()),
+            TraitItem::Fn(m) => (&m.sig.ident, Some(Shape::of(&m.sig)), m.default.is_some()),
+            TraitItem::Type(t) => (&t.ident, None, t.default.is_some()),
+            _ => continue,
+        };
+        let item = DeclItem {
+            ident: ident.clone(),
+            shape,
+        };
+        if has_default {
+            optional.push(item);
+        } else {
+            required.push(item);
+        }
+    }
+
+    // `$crate::path::to::module`, without the `crate` that the path may start with
+    let module = args
+        .decl_module
+        .iter()
+        .flat_map(|p| &p.segments)
+        .map(|s| &s.ident)
+        .filter(|i| *i != "crate");
+    let module = quote!($crate #(::#module)*);
+    let ident = &trait_def.ident;
+    let trait_path = args.trait_path(ident);
+    let name = naming::decl_macro_ident(ident);
+    let reexport = format_ident!("__ext_impl_{}", ident.unraw());
+    let cfg = args.cfg_attrs();
+    let doc = format!(
+        " Implement `{}` with `{}!(Type {{ ... }})` or `{}!(impl<T> Type<T> {{ ... }})`",
+        ident, name, name
+    );
+    Ok(quote! {
+        #(#cfg)*
+        #[doc(hidden)]
+        pub use ::ext_trait::__ext_impl as #reexport;
+
+        #(#cfg)*
+        #[doc = #doc]
+        #[macro_export]
+        macro_rules! #name {
+            ($($input:tt)*) => {
+                #module::#reexport! {
+                    [#module::#trait_path] [#(#required)*] [#(#optional)*] $($input)*
+                }
+            };
+        }
+    })
+}
+
+/// The input of `__ext_impl!`: `[path::Trait] [required items] [optional items]` and then either
+/// `Type { ... }` or `impl<...> Type where ... { ... }`
+pub struct DeclImpl {
+    trait_path: TokenStream2,
+    required: Vec<DeclItem>,
+    optional: Vec<DeclItem>,
+    item: ItemImpl,
+}
+
+fn bracketed_items(input: ParseStream) -> syn::Result<Vec<DeclItem>> {
+    let content;
+    syn::bracketed!(content in input);
+    let mut items = Vec::new();
+    while !content.is_empty() {
+        items.push(content.parse()?);
+    }
+    Ok(items)
+}
+
+impl Parse for DeclImpl {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        syn::bracketed!(content in input);
+        let trait_path = content.parse()?;
+        let required = bracketed_items(input)?;
+        let optional = bracketed_items(input)?;
+        let item = if input.peek(Token![impl]) {
+            input.parse()?
+        } else {
+            let rest: TokenStream2 = input.parse()?;
+            syn::parse2(quote!(impl #rest))?
+        };
+        Ok(DeclImpl {
+            trait_path,
+            required,
+            optional,
+            item,
+        })
+    }
+}
+
+/// Implement the trait of an `#[ext_decl]` with the items, which have to be the trait's
+pub fn implement(input: DeclImpl) -> syn::Result<TokenStream2> {
+    let DeclImpl {
+        trait_path,
+        required,
+        optional,
+        item,
+    } = input;
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(syn::Error::new_spanned(
+            path,
+            "the trait is added by the macro and can't be written",
+        ));
+    }
+
+    let mut errors: Vec<syn::Error> = Vec::new();
+    let idents: Vec<&Ident> = item.items.iter().filter_map(impl_item_ident).collect();
+    for ii in &item.items {
+        let ident = match impl_item_ident(ii) {
+            Some(ident) => ident,
+            None => continue,
+        };
+        let decl = match required.iter().chain(&optional).find(|d| d.ident == *ident) {
+            Some(decl) => decl,
+            None => {
+                errors.push(syn::Error::new(
+                    ident.span(),
+                    format!("the trait doesn't have an item `{}`", ident),
+                ));
+                continue;
+            }
+        };
+        let shape = match ii {
+            ImplItem::Fn(m) => Some(Shape::of(&m.sig)),
+            _ => None,
+        };
+        match (&decl.shape, &shape) {
+            (Some(expected), Some(found)) if expected != found => errors.push(syn::Error::new(
+                ident.span(),
+                format!(
+                    "`{}` has {} in the trait, but {} here",
+                    ident,
+                    expected.describe(),
+                    found.describe()
+                ),
+            )),
+            (Some(_), None) | (None, Some(_)) => errors.push(syn::Error::new(
+                ident.span(),
+                format!("`{}` is a different kind of item in the trait", ident),
+            )),
+            _ => (),
+        }
+    }
+    let missing: Vec<String> = required
+        .iter()
+        .filter(|r| !idents.contains(&&r.ident))
+        .map(|r| format!("`{}`", r.ident))
+        .collect();
+    if !missing.is_empty() {
+        errors.push(syn::Error::new_spanned(
+            &item.self_ty,
+            format!("missing items of the trait: {}", missing.join(", ")),
+        ));
+    }
+    if let Some(errors) = errors.into_iter().reduce(|mut all, e| {
+        all.combine(e);
+        all
+    }) {
+        return Err(errors);
+    }
+
+    let ItemImpl {
+        attrs,
+        defaultness,
+        unsafety,
+        generics,
+        self_ty,
+        items,
+        ..
+    } = item;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        #(#attrs)*
+        #defaultness #unsafety impl #impl_generics #trait_path for #self_ty #where_clause {
+            #(#items)*
+        }
+    })
+}
@@ -0,0 +1,304 @@
+use super::item_args::trait_item_attrs_mut;
+use super::naming::first_bound;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::ToTokens;
+use std::collections::HashMap;
+use syn::ext::IdentExt;
+use syn::{
+    Attribute, Expr, FnArg, Generics, ImplItem, ItemImpl, ItemTrait, LitStr, Meta, Pat, Path,
+    Signature, TraitItem, Type,
+};
+
+/// Tokens as they would be written by hand, e.g. `&'a Vec<u8>` instead of `& 'a Vec < u8 >`
+pub fn render(tokens: &impl ToTokens) -> String {
+    tokens
+        .to_token_stream()
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(" < ", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+        .replace("& ", "&")
+}
+
+/// A path without generic arguments, which is what intra-doc links point to
+fn link_destination(path: &Path) -> String {
+    let segments: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+    let leading = if path.leading_colon.is_some() {
+        "::"
+    } else {
+        ""
+    };
+    format!("{}{}", leading, segments.join("::"))
+}
+
+/// The predicates of all `#[cfg(...)]` attributes
+fn cfg_predicates(attrs: &[Attribute]) -> Vec<TokenStream2> {
+    attrs
+        .iter()
+        .filter(|a| a.path().is_ident("cfg"))
+        .filter_map(|a| a.parse_args().ok())
+        .collect()
+}
+
+fn doc_cfg_attr(flag: &Ident, predicates: &[TokenStream2]) -> Option<Attribute> {
+    match predicates {
+        [] => None,
+        [p] => Some(syn::parse_quote!(#[cfg_attr(#flag, doc(cfg(#p)))])),
+        ps => Some(syn::parse_quote!(#[cfg_attr(#flag, doc(cfg(all(#(#ps),*))))])),
+    }
+}
+
+fn is_doc_comment(attr: &Attribute) -> bool {
+    matches!(&attr.meta, Meta::NameValue(nv) if nv.path.is_ident("doc"))
+}
+
+/// Remove the doc comments (but not other `#[doc(...)]`s) from the trait and its items
+pub fn strip_doc_comments(trait_def: &mut ItemTrait) {
+    trait_def.attrs.retain(|a| !is_doc_comment(a));
+    for ti in &mut trait_def.items {
+        if let Some(attrs) = trait_item_attrs_mut(ti) {
+            attrs.retain(|a| !is_doc_comment(a));
+        }
+    }
+}
+
+/// Replace the doc comments of the named items of the trait, for `#[ext(trait_doc = "...")]`
+pub fn set_trait_docs(trait_def: &mut ItemTrait, docs: &[(Ident, LitStr)]) {
+    if docs.is_empty() {
+        return;
+    }
+    let docs: HashMap<&Ident, &LitStr> = docs.iter().map(|(i, doc)| (i, doc)).collect();
+    for ti in &mut trait_def.items {
+        let (ident, attrs) = match ti {
+            TraitItem::Const(c) => (&c.ident, &mut c.attrs),
+            TraitItem::Fn(m) => (&m.sig.ident, &mut m.attrs),
+            TraitItem::Type(t) => (&t.ident, &mut t.attrs),
+            _ => continue,
+        };
+        if let Some(doc) = docs.get(ident) {
+            attrs.retain(|a| !is_doc_comment(a));
+            attrs.push(syn::parse_quote!(#[doc = #doc]));
+        }
+    }
+}
+
+/// With `require_docs`, an error for the trait (at `impl_span`) and each of its items that don't
+/// have a doc comment
+pub fn require_docs(trait_def: &ItemTrait, impl_span: Span) -> syn::Result<()> {
+    let mut errors = Vec::new();
+    if !trait_def.attrs.iter().any(is_doc_comment) {
+        errors.push(syn::Error::new(
+            impl_span,
+            "`require_docs`: the trait needs a doc comment (on the impl)",
+        ));
+    }
+    for ti in &trait_def.items {
+        let (ident, attrs) = match ti {
+            TraitItem::Const(c) => (&c.ident, &c.attrs),
+            TraitItem::Fn(m) => (&m.sig.ident, &m.attrs),
+            TraitItem::Type(t) => (&t.ident, &t.attrs),
+            _ => continue,
+        };
+        if !attrs.iter().any(is_doc_comment) {
+            errors.push(syn::Error::new(
+                ident.span(),
+                format!("`require_docs`: `{}` needs a doc comment", ident.unraw()),
+            ));
+        }
+    }
+    match errors.into_iter().reduce(|mut all, e| {
+        all.combine(e);
+        all
+    }) {
+        Some(errors) => Err(errors),
+        None => Ok(()),
+    }
+}
+
+/// Mirror the `#[cfg]`s of the trait and its items as `#[doc(cfg)]`s when `flag` is set
+pub fn add_doc_cfg(trait_def: &mut ItemTrait, flag: &Ident) {
+    let predicates = cfg_predicates(&trait_def.attrs);
+    trait_def.attrs.extend(doc_cfg_attr(flag, &predicates));
+
+    for ti in &mut trait_def.items {
+        if let Some(attrs) = trait_item_attrs_mut(ti) {
+            let predicates = cfg_predicates(attrs);
+            attrs.extend(doc_cfg_attr(flag, &predicates));
+        }
+    }
+}
+
+/// A call of the method with its parameter names as arguments, e.g. `value.split(at)`
+fn example_call(trait_name: &Ident, sig: &Signature) -> String {
+    let args: Vec<String> = sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(t) => Some(match &*t.pat {
+                Pat::Ident(p) => p.ident.unraw().to_string(),
+                _ => "arg".to_string(),
+            }),
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+    let receiver = match sig.receiver() {
+        Some(_) => "value".to_string(),
+        None => trait_name.to_string(),
+    };
+    let sep = if sig.receiver().is_some() { "." } else { "::" };
+    format!("{}{}{}({})", receiver, sep, sig.ident, args.join(", "))
+}
+
+/// Append a "Usage" section to the docs of the trait, which shows how to import it (from `module`)
+/// and call its first method
+///
+/// With an `example` value, the code is a doctest that calls the first method that takes no
+/// arguments (besides `self`) on it.
+pub fn add_usage(
+    trait_def: &mut ItemTrait,
+    module: &Path,
+    example: Option<&Expr>,
+) -> syn::Result<()> {
+    let name = &trait_def.ident;
+    let mut lines = vec![
+        String::new(),
+        " # Usage".to_string(),
+        " The trait has to be imported for its methods to be available:".to_string(),
+        if example.is_some() {
+            " ```"
+        } else {
+            " ```ignore"
+        }
+        .to_string(),
+        format!(" use {}::{};", render(module), name),
+    ];
+    let mut methods = trait_def.items.iter().filter_map(|ti| match ti {
+        TraitItem::Fn(m) => Some(&m.sig),
+        _ => None,
+    });
+    match example {
+        Some(example) => {
+            let sig = methods
+                .find(|sig| sig.receiver().is_some() && sig.inputs.len() == 1)
+                .ok_or_else(|| {
+                    syn::Error::new_spanned(
+                        example,
+                        "`doctest_example` needs a method that takes no arguments besides `self`",
+                    )
+                })?;
+            lines.push(String::new());
+            lines.push(format!(" let mut value = {};", render(example)));
+            lines.push(format!(" let _ = {};", example_call(name, sig)));
+        }
+        None => {
+            if let Some(sig) = methods.next() {
+                lines.push(String::new());
+                lines.push(format!(" {};", example_call(name, sig)));
+            }
+        }
+    }
+    lines.push(" ```".to_string());
+    trait_def.attrs.extend(
+        lines
+            .iter()
+            .map(|line| -> Attribute { syn::parse_quote!(#[doc = #line]) }),
+    );
+    Ok(())
+}
+
+/// An intra-doc link to the type (or, for slices, the primitive) that `ty` is about
+fn type_link(ty: &Type, generics: &Generics) -> Option<String> {
+    let destination = match ty {
+        Type::Reference(r) => return type_link(&r.elem, generics),
+        Type::Paren(p) => return type_link(&p.elem, generics),
+        Type::Group(g) => return type_link(&g.elem, generics),
+        Type::Slice(_) => "slice".to_string(),
+        Type::Array(_) => "array".to_string(),
+        Type::Path(p) if p.qself.is_none() => {
+            if let Some(ident) = p.path.get_ident() {
+                if generics.type_params().any(|param| param.ident == *ident) {
+                    return None;
+                }
+            }
+            link_destination(&p.path)
+        }
+        _ => return None,
+    };
+    Some(destination)
+}
+
+/// Link the type that the trait extends from the docs of the trait
+pub fn add_target_link(trait_def: &mut ItemTrait, self_ty: &Type, generics: &Generics) {
+    let line = match self_ty {
+        Type::Path(p) if p.qself.is_none() && p.path.get_ident().is_some() => {
+            match first_bound(p.path.get_ident().unwrap(), generics) {
+                Some(bound) => Some(format!(
+                    " Extension methods for any [`{}`]({}).",
+                    render(bound),
+                    link_destination(bound)
+                )),
+                None if generics
+                    .type_params()
+                    .any(|param| p.path.is_ident(&param.ident)) =>
+                {
+                    None
+                }
+                None => type_link(self_ty, generics).map(|dest| {
+                    format!(" Extension methods for [`{}`]({}).", render(self_ty), dest)
+                }),
+            }
+        }
+        _ => Some(match type_link(self_ty, generics) {
+            Some(dest) => format!(" Extension methods for [`{}`]({}).", render(self_ty), dest),
+            None => format!(" Extension methods for `{}`.", render(self_ty)),
+        }),
+    };
+    if let Some(line) = line {
+        append_paragraph(trait_def, &line);
+    }
+}
+
+/// Add a paragraph at the end of the docs of the trait
+fn append_paragraph(trait_def: &mut ItemTrait, line: &str) {
+    if trait_def.attrs.iter().any(is_doc_comment) {
+        trait_def.attrs.push(syn::parse_quote!(#[doc = ""]));
+    }
+    trait_def.attrs.push(syn::parse_quote!(#[doc = #line]));
+}
+
+/// Point out that the trait is sealed
+pub fn add_sealed_note(trait_def: &mut ItemTrait) {
+    append_paragraph(
+        trait_def,
+        " This trait is sealed and cannot be implemented outside of this crate.",
+    );
+}
+
+/// Link back to the trait from the (documented) methods of the impl
+pub fn add_see_also(item: &mut ItemImpl, trait_path: &Path) {
+    let trait_name = match trait_path.segments.last() {
+        Some(last) => last.ident.unraw(),
+        None => return,
+    };
+    let lines = [
+        String::new(),
+        format!(
+            " See also [`{}`]({}).",
+            trait_name,
+            link_destination(trait_path)
+        ),
+    ];
+    for ii in &mut item.items {
+        if let ImplItem::Fn(m) = ii {
+            if m.attrs.iter().any(|a| a.path().is_ident("doc")) {
+                m.attrs.extend(
+                    lines
+                        .iter()
+                        .map(|line| -> Attribute { syn::parse_quote!(#[doc = #line]) }),
+                );
+            }
+        }
+    }
+}
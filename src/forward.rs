@@ -0,0 +1,724 @@
+use super::args::ExtArgs;
+use super::docs::render;
+use super::naming;
+use super::process_impl::{idents, is_unsized, replace_self};
+use super::routing::item_cfgs;
+use super::tokens_eq;
+use proc_macro2::{Group, Span, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    FnArg, GenericParam, Ident, ImplItem, ItemImpl, ItemStruct, ItemTrait, Member, Path, Receiver,
+    ReturnType, Signature, Token, TraitItem, Type, TypeParamBound, WherePredicate,
+};
+
+/// Give all arguments (other than `self`) plain names, so that they can be passed on
+fn name_args(sig: &mut Signature, span: Span) -> Vec<TokenStream2> {
+    let mut names = Vec::new();
+    for (i, arg) in sig.inputs.iter_mut().enumerate() {
+        match arg {
+            FnArg::Receiver(_) => names.push(quote!(self)),
+            FnArg::Typed(t) => {
+                let name = format_ident!("__arg{}", i, span = span);
+                *t.pat = syn::parse_quote!(#name);
+                names.push(name.into_token_stream());
+            }
+        }
+    }
+    names
+}
+
+/// The generic arguments of a call of the function, if they can be given explicitly (which isn't
+/// the case with `impl Trait` arguments)
+fn turbofish(sig: &Signature) -> TokenStream2 {
+    let has_impl_args = sig.inputs.iter().any(|arg| match arg {
+        FnArg::Typed(t) => idents(&t.ty).contains("impl"),
+        FnArg::Receiver(_) => false,
+    });
+    let params: Vec<_> = sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|p| match p {
+            GenericParam::Type(t) => Some(&t.ident),
+            GenericParam::Const(c) => Some(&c.ident),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    if has_impl_args || params.is_empty() {
+        TokenStream2::new()
+    } else {
+        quote!(::<#(#params),*>)
+    }
+}
+
+/// Make the items of the impl call the items with the same name in the inherent impl of the self
+/// type, which are found first by `<Type>::item` paths
+pub fn to_inherent(item: &mut ItemImpl, span: Span) -> syn::Result<()> {
+    let self_ty = &item.self_ty;
+    for ii in &mut item.items {
+        match ii {
+            ImplItem::Fn(m) => {
+                let name = m.sig.ident.clone();
+                let turbofish = turbofish(&m.sig);
+                let args = name_args(&mut m.sig, span);
+                m.block = syn::parse_quote!({ <#self_ty>::#name #turbofish(#(#args),*) });
+            }
+            ImplItem::Const(c) => {
+                let name = &c.ident;
+                c.expr = syn::parse_quote!(<#self_ty>::#name);
+            }
+            ImplItem::Type(t) => {
+                return Err(syn::Error::new(
+                    t.ident.span(),
+                    "inherent impls can't have associated types",
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Implementing the trait by calling the impl of another type
+pub struct Delegation<'a> {
+    /// The type whose impl is called
+    pub target: &'a Type,
+    pub trait_path: &'a Path,
+    /// The option that asked for this, for error messages
+    pub option: &'static str,
+    /// The span of the argument names
+    pub span: Span,
+}
+
+impl Delegation<'_> {
+    fn unsupported(&self, tokens: &impl ToTokens, what: &str) -> syn::Error {
+        syn::Error::new_spanned(
+            tokens,
+            format!("`{}` doesn't support {}", self.option, what),
+        )
+    }
+
+    /// The items of the impl, where the methods that take `self` get the body
+    /// `body(method, receiver, args)`, with `receiver` being the receiver of the trait method
+    fn items_with(
+        &self,
+        trait_def: &ItemTrait,
+        mut body: impl FnMut(&Signature, &Receiver, &[Ident]) -> syn::Result<TokenStream2>,
+    ) -> syn::Result<Vec<TokenStream2>> {
+        let Delegation {
+            target, trait_path, ..
+        } = self;
+        let mut items = Vec::new();
+        for ti in &trait_def.items {
+            match ti {
+                TraitItem::Const(c) => {
+                    let (ident, ty) = (&c.ident, &c.ty);
+                    let cfg = item_cfgs(&c.attrs);
+                    items.push(quote! {
+                        #(#cfg)*
+                        const #ident: #ty = <#target as #trait_path>::#ident;
+                    });
+                }
+                TraitItem::Type(t) => {
+                    let ident = &t.ident;
+                    let cfg = item_cfgs(&t.attrs);
+                    let (impl_generics, ty_generics, where_clause) = t.generics.split_for_impl();
+                    items.push(quote! {
+                        #(#cfg)*
+                        type #ident #impl_generics = <#target as #trait_path>::#ident #ty_generics
+                            #where_clause;
+                    });
+                }
+                TraitItem::Fn(m) => {
+                    let mut sig = m.sig.clone();
+                    if let ReturnType::Type(_, ty) = &sig.output {
+                        if idents(ty).contains("Self") {
+                            return Err(self.unsupported(ty, "methods that return `Self`"));
+                        }
+                    }
+                    let mut args = Vec::new();
+                    let mut receiver = None;
+                    for (i, arg) in sig.inputs.iter_mut().enumerate() {
+                        match arg {
+                            FnArg::Receiver(r) if r.colon_token.is_some() => {
+                                return Err(
+                                    self.unsupported(&r.ty, "arguments that mention `Self`")
+                                );
+                            }
+                            FnArg::Receiver(r) => {
+                                receiver = Some(r.clone());
+                                r.mutability = r.mutability.filter(|_| r.reference.is_some());
+                            }
+                            FnArg::Typed(t) => {
+                                if idents(&t.ty).contains("Self") {
+                                    return Err(
+                                        self.unsupported(&t.ty, "arguments that mention `Self`")
+                                    );
+                                }
+                                let arg = format_ident!("__arg{}", i, span = self.span);
+                                *t.pat = syn::parse_quote!(#arg);
+                                args.push(arg);
+                            }
+                        }
+                    }
+                    let name = &sig.ident;
+                    let body = match receiver {
+                        Some(receiver) => body(&m.sig, &receiver, &args)?,
+                        None => quote!(<#target as #trait_path>::#name(#(#args),*)),
+                    };
+                    let cfg = item_cfgs(&m.attrs);
+                    items.push(quote!(#(#cfg)* #sig { #body }));
+                }
+                _ => {}
+            }
+        }
+        Ok(items)
+    }
+
+    /// The items of an impl for a struct that stores the value in `field`, where
+    /// `before(method, args)` is put in front of the call in the methods that take `self`
+    pub fn items(
+        &self,
+        trait_def: &ItemTrait,
+        field: TokenStream2,
+        mut before: impl FnMut(&Ident, &[Ident]) -> TokenStream2,
+    ) -> syn::Result<Vec<TokenStream2>> {
+        let Delegation {
+            target, trait_path, ..
+        } = self;
+        self.items_with(trait_def, |sig, r, args| {
+            let receiver = match (&r.reference, &r.mutability) {
+                (Some(_), Some(_)) => quote!(&mut self.#field),
+                (Some(_), None) => quote!(&self.#field),
+                (None, _) => quote!(self.#field),
+            };
+            let name = &sig.ident;
+            let before = before(name, args);
+            Ok(quote! {
+                #before
+                <#target as #trait_path>::#name(#receiver, #(#args),*)
+            })
+        })
+    }
+}
+
+/// The supertraits that generated types need (empty) impls of, which leaves out the ones added by
+/// `dyn_clone` and `downcast`, since those have blanket impls
+pub fn own_supertraits<'a>(args: &ExtArgs, trait_def: &'a ItemTrait) -> Vec<&'a TypeParamBound> {
+    let mut blanket: Vec<TypeParamBound> = Vec::new();
+    if args.dyn_clone {
+        blanket.push(syn::parse_quote!(dyn_clone::DynClone));
+    }
+    if args.downcast {
+        blanket.push(syn::parse_quote!(downcast_rs::Downcast));
+    }
+    trait_def
+        .supertraits
+        .iter()
+        .filter(|s| !blanket.iter().any(|b| tokens_eq(*s, b)))
+        .collect()
+}
+
+/// A tuple struct around the self type that implements the trait by calling the impl of the self
+/// type, along with `Deref` and `From` impls
+pub fn newtype(
+    args: &ExtArgs,
+    trait_def: &ItemTrait,
+    item: &ItemImpl,
+    name: &Ident,
+) -> syn::Result<TokenStream2> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "`newtype` doesn't support generic impls",
+        ));
+    }
+    let self_ty = &*item.self_ty;
+    let trait_path = args.trait_path(&trait_def.ident);
+    let delegation = Delegation {
+        target: self_ty,
+        trait_path: &trait_path,
+        option: "newtype",
+        span: args.span(),
+    };
+    let items = delegation.items(trait_def, quote!(0), |_, _| TokenStream2::new())?;
+
+    let stored = if is_unsized(self_ty) {
+        quote!(Box<#self_ty>)
+    } else {
+        quote!(#self_ty)
+    };
+    let vis = args.vis();
+    let cfg = args.cfg_attrs();
+    let glue = args.glue_attrs();
+    let glue_all = quote!(#(#glue)*);
+    let supertraits = own_supertraits(args, trait_def);
+    // `DynClone` is implemented for all `Clone` types
+    let derive = if args.dyn_clone {
+        quote!(#[derive(Clone)])
+    } else {
+        TokenStream2::new()
+    };
+    let doc = format!(
+        " A wrapper around `{}` that implements [`{}`]",
+        render(self_ty),
+        trait_def.ident
+    );
+    Ok(quote! {
+        #(#cfg)*
+        #[doc = #doc]
+        #derive
+        #vis struct #name(pub #stored);
+
+        #(#glue)*
+        impl std::ops::Deref for #name {
+            type Target = #self_ty;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        #(#glue)*
+        impl From<#stored> for #name {
+            fn from(inner: #stored) -> Self {
+                #name(inner)
+            }
+        }
+
+        #(
+            #glue_all
+            impl #supertraits for #name {}
+        )*
+
+        #(#glue)*
+        impl #trait_path for #name {
+            #(#items)*
+        }
+    })
+}
+
+/// The types that `lift(...)` implements the trait for
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Lift {
+    /// `Option<Type>`
+    Option,
+    /// `Result<Type, E>` for any `E`
+    Result,
+}
+
+impl Lift {
+    pub fn from_ident(ident: &Ident) -> syn::Result<Self> {
+        match ident.to_string().as_str() {
+            "Option" => Ok(Lift::Option),
+            "Result" => Ok(Lift::Result),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                "expected `Option` or `Result`",
+            )),
+        }
+    }
+}
+
+/// An impl of the trait for `Option<Type>` or `Result<Type, E>` that calls the impl of `Type` for
+/// `Some` and `Ok` values and returns `Default::default()` otherwise
+pub fn lift(
+    item: &ItemImpl,
+    trait_def: &ItemTrait,
+    lift: Lift,
+    span: Span,
+) -> syn::Result<ItemImpl> {
+    let target = &*item.self_ty;
+    let trait_path = &item.trait_.as_ref().expect("the impl is a trait impl").1;
+    let delegation = Delegation {
+        target,
+        trait_path,
+        option: "lift",
+        span,
+    };
+    let (present, absent) = match lift {
+        Lift::Option => (quote!(Some), quote!(None)),
+        Lift::Result => (quote!(Ok), quote!(Err(_))),
+    };
+    let inner = Ident::new("__inner", span);
+    let items = delegation.items_with(trait_def, |sig, _, args| {
+        if let ReturnType::Type(_, ty) = &sig.output {
+            let mentioned = idents(ty);
+            if mentioned.contains("impl")
+                || sig
+                    .generics
+                    .type_params()
+                    .any(|p| mentioned.contains(&p.ident.to_string()))
+            {
+                return Err(delegation.unsupported(ty, "generic return types"));
+            }
+        }
+        let name = &sig.ident;
+        Ok(quote! {
+            match self {
+                #present(#inner) => <#target as #trait_path>::#name(#inner, #(#args),*),
+                #absent => Default::default(),
+            }
+        })
+    })?;
+
+    let mut generics = item.generics.clone();
+    // `Self` would be the lifted type now
+    if let Some(c) = &mut generics.where_clause {
+        for p in &mut c.predicates {
+            if let WherePredicate::Type(t) = p {
+                replace_self(&mut t.bounded_ty, target);
+            }
+        }
+    }
+    let self_ty = match lift {
+        Lift::Option => quote!(Option<#target>),
+        Lift::Result => {
+            generics.params.push(syn::parse_quote!(__LiftErr));
+            quote!(Result<#target, __LiftErr>)
+        }
+    };
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    syn::parse2(quote! {
+        impl #impl_generics #trait_path for #self_ty #where_clause {
+            #(#items)*
+        }
+    })
+}
+
+/// The smart pointers that `forward(...)` implements the trait for
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Forward {
+    /// `Cow<'_, Type>`
+    Cow,
+}
+
+impl Forward {
+    pub fn from_ident(ident: &Ident) -> syn::Result<Self> {
+        match ident.to_string().as_str() {
+            "Cow" => Ok(Forward::Cow),
+            _ => Err(syn::Error::new(ident.span(), "expected `Cow`")),
+        }
+    }
+}
+
+/// An impl of the trait for `Cow<'_, Type>` that calls the impl of `Type` with the borrowed value
+pub fn forward(
+    item: &ItemImpl,
+    trait_def: &ItemTrait,
+    forward: Forward,
+    span: Span,
+) -> syn::Result<ItemImpl> {
+    let target = &*item.self_ty;
+    let trait_path = &item.trait_.as_ref().expect("the impl is a trait impl").1;
+    let delegation = Delegation {
+        target,
+        trait_path,
+        option: "forward(Cow)",
+        span,
+    };
+    let items = delegation.items_with(trait_def, |sig, receiver, args| {
+        if receiver.reference.is_none() || receiver.mutability.is_some() {
+            return Err(delegation.unsupported(receiver, "methods that don't take `&self`"));
+        }
+        let name = &sig.ident;
+        Ok(quote!(<#target as #trait_path>::#name(&**self, #(#args),*)))
+    })?;
+
+    let mut generics = item.generics.clone();
+    // `Self` would be the `Cow` now
+    if let Some(c) = &mut generics.where_clause {
+        for p in &mut c.predicates {
+            if let WherePredicate::Type(t) = p {
+                replace_self(&mut t.bounded_ty, target);
+            }
+        }
+    }
+    let self_ty = match forward {
+        Forward::Cow => {
+            let lifetime = syn::Lifetime::new("'__cow", span);
+            generics.params.insert(0, syn::parse_quote!(#lifetime));
+            generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(#target: std::borrow::ToOwned));
+            quote!(std::borrow::Cow<#lifetime, #target>)
+        }
+    };
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    syn::parse2(quote! {
+        impl #impl_generics #trait_path for #self_ty #where_clause {
+            #(#items)*
+        }
+    })
+}
+
+/// Free functions that call the methods of the trait, with the receiver (if any) as the first
+/// argument `recv`
+pub fn free_fns(
+    args: &ExtArgs,
+    trait_def: &ItemTrait,
+    self_ty: &Type,
+) -> syn::Result<TokenStream2> {
+    let trait_path = args.trait_path(&trait_def.ident);
+    let (_, trait_args, _) = trait_def.generics.split_for_impl();
+    let vis = args.vis();
+    let cfg = args.cfg_attrs();
+    let recv = Ident::new("recv", args.span());
+    let mut fns = TokenStream2::new();
+    for ti in &trait_def.items {
+        let m = match ti {
+            TraitItem::Fn(m) => m,
+            _ => continue,
+        };
+        let mut sig = m.sig.clone();
+        let turbofish = turbofish(&sig);
+        let mut call_args = Vec::new();
+        for (i, arg) in sig.inputs.iter_mut().enumerate() {
+            let name = match arg {
+                FnArg::Receiver(r) => {
+                    let ty = match &r.reference {
+                        Some((and, lifetime)) => {
+                            let mutability = &r.mutability;
+                            quote!(#and #lifetime #mutability #self_ty)
+                        }
+                        None => {
+                            let mut ty = (*r.ty).clone();
+                            replace_self(&mut ty, self_ty);
+                            ty.into_token_stream()
+                        }
+                    };
+                    *arg = syn::parse_quote!(#recv: #ty);
+                    recv.clone()
+                }
+                FnArg::Typed(t) => {
+                    replace_self(&mut t.ty, self_ty);
+                    let name = match &*t.pat {
+                        syn::Pat::Ident(p) if p.ident == "self" => recv.clone(),
+                        _ => format_ident!("__arg{}", i, span = args.span()),
+                    };
+                    *t.pat = syn::parse_quote!(#name);
+                    name
+                }
+            };
+            call_args.push(name);
+        }
+        if let ReturnType::Type(_, ty) = &mut sig.output {
+            replace_self(ty, self_ty);
+        }
+
+        let method_generics = std::mem::take(&mut sig.generics);
+        let generics = &mut sig.generics;
+        let all = trait_def
+            .generics
+            .params
+            .iter()
+            .chain(&method_generics.params);
+        let (lifetimes, others): (Vec<_>, Vec<_>) =
+            all.partition(|p| matches!(p, GenericParam::Lifetime(_)));
+        generics.params = lifetimes.into_iter().chain(others).cloned().collect();
+        let predicates = trait_def
+            .generics
+            .where_clause
+            .iter()
+            .chain(&method_generics.where_clause)
+            .flat_map(|c| c.predicates.iter().cloned());
+        for mut p in predicates {
+            if let WherePredicate::Type(t) = &mut p {
+                replace_self(&mut t.bounded_ty, self_ty);
+            }
+            generics.make_where_clause().predicates.push(p);
+        }
+        if idents(&sig).contains("Self") {
+            return Err(syn::Error::new(
+                m.sig.ident.span(),
+                "`also_fns` doesn't support associated types of `Self`",
+            ));
+        }
+
+        let name = &m.sig.ident;
+        let doc = format!(" Calls [`{}::{}`]", render(&trait_path), name);
+        let item_cfg = item_cfgs(&m.attrs);
+        fns.extend(quote! {
+            #(#cfg)*
+            #(#item_cfg)*
+            #[doc = #doc]
+            #vis #sig {
+                <#self_ty as #trait_path #trait_args>::#name #turbofish(#(#call_args),*)
+            }
+        });
+    }
+    Ok(fns)
+}
+
+/// Replace the identifiers in `tokens` that are named like one of `vars` with its tokens
+fn substitute(tokens: TokenStream2, vars: &[(&str, TokenStream2)]) -> TokenStream2 {
+    tokens
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Group(g) => {
+                let mut group = Group::new(g.delimiter(), substitute(g.stream(), vars));
+                group.set_span(g.span());
+                TokenTree::Group(group).into()
+            }
+            TokenTree::Ident(i) => match vars.iter().find(|(name, _)| i == name) {
+                Some((_, tokens)) => tokens.clone(),
+                None => TokenTree::Ident(i).into(),
+            },
+            tt => tt.into(),
+        })
+        .collect()
+}
+
+/// The `macro_rules!` macro that `#[ext_delegate]` calls, which knows the items of the trait
+///
+/// It's re-exported with the name of the trait, so that it's found wherever the trait is. It's
+/// called like `Trait! { [<impl generics>] [Wrapper<...>] [where ...] [path::Trait]
+/// [FieldType] [field] }` and expands to an impl of the trait for the wrapper that calls the impl
+/// of the field type.
+pub fn delegate_macro(args: &ExtArgs, trait_def: &ItemTrait) -> syn::Result<TokenStream2> {
+    if !trait_def.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &trait_def.generics,
+            "`delegatable` doesn't support generic traits",
+        ));
+    }
+    let delegation = Delegation {
+        target: &syn::parse_quote!(__ExtDelegateTarget),
+        trait_path: &syn::parse_quote!(__ExtDelegateTrait),
+        option: "delegatable",
+        span: args.span(),
+    };
+    let items = delegation.items(trait_def, quote!(__ext_delegate_field), |_, _| {
+        TokenStream2::new()
+    })?;
+    let items = substitute(
+        quote!(#(#items)*),
+        &[
+            ("__ExtDelegateTarget", quote!($target)),
+            ("__ExtDelegateTrait", quote!($trait)),
+            ("__ext_delegate_field", quote!($field)),
+        ],
+    );
+
+    let ident = &trait_def.ident;
+    let name = naming::delegate_ident(ident);
+    let cfg = args.cfg_attrs();
+    Ok(quote! {
+        #(#cfg)*
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #name {
+            (
+                [$($generics:tt)*] [$self_ty:ty] [$($where:tt)*] [$trait:path] [$target:ty]
+                [$field:tt]
+            ) => {
+                impl $($generics)* $trait for $self_ty $($where)* {
+                    #items
+                }
+            };
+        }
+
+        #(#cfg)*
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #name as #ident;
+    })
+}
+
+/// The arguments of `#[ext_delegate(...)]`: the trait and, optionally, `field = name`
+pub struct DelegateArgs {
+    trait_path: Path,
+    field: Option<Member>,
+}
+
+impl Parse for DelegateArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let trait_path = input.parse()?;
+        let mut field = None;
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+        if !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key != "field" {
+                return Err(syn::Error::new(key.span(), "expected `field = ...`"));
+            }
+            input.parse::<Token![=]>()?;
+            field = Some(input.parse()?);
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(DelegateArgs { trait_path, field })
+    }
+}
+
+/// The struct along with an impl of the trait that calls the impl of the type of the field, which
+/// is generated by the macro that `delegatable` put next to the trait
+pub fn delegate(args: &DelegateArgs, item: &ItemStruct) -> syn::Result<TokenStream2> {
+    let DelegateArgs { trait_path, field } = args;
+    let last = trait_path
+        .segments
+        .last()
+        .expect("paths have at least one segment");
+    if !last.arguments.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &last.arguments,
+            "`ext_delegate` doesn't support generic traits",
+        ));
+    }
+
+    let fields: Vec<_> = item.fields.iter().collect();
+    let (member, field_ty) = match field {
+        Some(member) => {
+            let found = fields
+                .iter()
+                .enumerate()
+                .find(|(i, f)| match (member, &f.ident) {
+                    (Member::Named(name), Some(ident)) => name == ident,
+                    (Member::Unnamed(index), None) => index.index as usize == *i,
+                    _ => false,
+                });
+            match found {
+                Some((_, f)) => (member.clone(), &f.ty),
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        member,
+                        format!("`{}` doesn't have this field", item.ident),
+                    ))
+                }
+            }
+        }
+        None => match fields.as_slice() {
+            [f] => {
+                let member = match &f.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(0.into()),
+                };
+                (member, &f.ty)
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    trait_path,
+                    "`field = ...` is needed for structs that don't have exactly one field",
+                ))
+            }
+        },
+    };
+
+    let mut generics = item.generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(syn::parse_quote!(#field_ty: #trait_path));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let ident = &item.ident;
+    Ok(quote! {
+        #item
+
+        #trait_path! {
+            [#impl_generics] [#ident #ty_generics] [#where_clause] [#trait_path] [#field_ty]
+            [#member]
+        }
+    })
+}
@@ -1,39 +1,64 @@
+use super::process_impl::self_type_to_self;
+use super::routing::ImplOnly;
 use super::Token;
 use proc_macro2::{Ident, Span};
 use quote::ToTokens;
+use std::collections::{HashMap, HashSet};
 use syn::punctuated::Punctuated;
+use syn::visit_mut::VisitMut;
 use syn::{
-    ImplItem, ImplItemConst, ImplItemMacro, ImplItemMethod, ImplItemType, ItemImpl, ItemTrait,
-    TraitItem, TraitItemConst, TraitItemMacro, TraitItemMethod, TraitItemType, Visibility,
+    CapturedParam, ImplItem, ImplItemConst, ImplItemFn, ImplItemMacro, ImplItemType, ItemImpl,
+    ItemTrait, PreciseCapture, TraitItem, TraitItemConst, TraitItemFn, TraitItemMacro,
+    TraitItemType, TypeParam, Visibility,
 };
 
-fn convert_method(m: ImplItemMethod) -> TraitItemMethod {
-    TraitItemMethod {
-        attrs: m.attrs,
-        sig: m.sig,
+/// Adds `Self` to the `use<...>` bounds of `impl Trait` types, which have to name it in a trait
+struct CaptureSelf;
+
+impl VisitMut for CaptureSelf {
+    fn visit_precise_capture_mut(&mut self, capture: &mut PreciseCapture) {
+        let captures_self = capture
+            .params
+            .iter()
+            .any(|p| matches!(p, CapturedParam::Ident(i) if i == "Self"));
+        if !captures_self {
+            let ident = Ident::new("Self", Span::call_site());
+            capture.params.push(CapturedParam::Ident(ident));
+        }
+    }
+}
+
+fn convert_method(m: &ImplItemFn) -> TraitItemFn {
+    let mut sig = m.sig.clone();
+    CaptureSelf.visit_return_type_mut(&mut sig.output);
+    // the body is never cloned
+    TraitItemFn {
+        attrs: m.attrs.clone(),
+        sig,
         default: None,
         semi_token: Some(Token![;](Span::call_site())),
     }
 }
 
-fn convert_constant(c: ImplItemConst) -> TraitItemConst {
+fn convert_constant(c: &ImplItemConst) -> TraitItemConst {
     TraitItemConst {
-        attrs: c.attrs,
+        attrs: c.attrs.clone(),
         const_token: c.const_token,
-        ident: c.ident,
+        ident: c.ident.clone(),
+        generics: c.generics.clone(),
         colon_token: c.colon_token,
-        ty: c.ty,
+        ty: c.ty.clone(),
         default: None,
         semi_token: c.semi_token,
     }
 }
 
-fn convert_type(t: ImplItemType) -> TraitItemType {
+fn convert_type(t: &ImplItemType) -> TraitItemType {
     TraitItemType {
-        attrs: t.attrs,
+        attrs: t.attrs.clone(),
         type_token: t.type_token,
-        ident: t.ident,
-        generics: t.generics,
+        ident: t.ident.clone(),
+        generics: t.generics.clone(),
         colon_token: None,
         bounds: Punctuated::new(),
         default: None,
@@ -41,21 +66,21 @@ fn convert_type(t: ImplItemType) -> TraitItemType {
     }
 }
 
-fn convert_macro(m: ImplItemMacro) -> TraitItemMacro {
+fn convert_macro(m: &ImplItemMacro) -> TraitItemMacro {
     TraitItemMacro {
-        attrs: m.attrs,
-        mac: m.mac,
+        attrs: m.attrs.clone(),
+        mac: m.mac.clone(),
         semi_token: m.semi_token,
     }
 }
 
-fn convert_item(i: ImplItem) -> TraitItem {
+fn convert_item(i: &ImplItem) -> TraitItem {
     match i {
         ImplItem::Const(c) => TraitItem::Const(convert_constant(c)),
-        ImplItem::Method(m) => TraitItem::Method(convert_method(m)),
+        ImplItem::Fn(m) => TraitItem::Fn(convert_method(m)),
         ImplItem::Type(t) => TraitItem::Type(convert_type(t)),
         ImplItem::Macro(m) => TraitItem::Macro(convert_macro(m)),
-        ImplItem::Verbatim(s) => TraitItem::Verbatim(s),
+        ImplItem::Verbatim(s) => TraitItem::Verbatim(s.clone()),
 
         // at the time of writing this, all valid ImplItems are covered above
         i => unimplemented!("Unsupported item: {}", i.into_token_stream()),
@@ -63,18 +88,139 @@ fn convert_item(i: ImplItem) -> TraitItem {
 }
 
 /// Make a trait out of the inherent impl
-pub fn to_trait(i: ItemImpl, vis: Visibility, trait_ident: Ident) -> ItemTrait {
+pub fn to_trait(
+    i: &ItemImpl,
+    vis: Visibility,
+    trait_ident: Ident,
+    impl_only: &ImplOnly,
+) -> ItemTrait {
+    let mut attrs = i.attrs.clone();
+    impl_only.strip(&mut attrs);
     ItemTrait {
-        attrs: i.attrs,
+        attrs,
         vis,
         unsafety: i.unsafety,
         auto_token: None,
+        restriction: None,
         trait_token: Token![trait](Span::call_site()),
         ident: trait_ident,
-        generics: i.generics,
+        generics: i.generics.clone(),
         colon_token: None,
         supertraits: Punctuated::new(),
         brace_token: i.brace_token,
-        items: i.items.into_iter().map(convert_item).collect(),
+        items: i
+            .items
+            .iter()
+            .map(convert_item)
+            .map(|ti| impl_only.strip_trait_item(ti))
+            .collect(),
+    }
+}
+
+/// Give the type parameters of the trait defaults (which impls can't have)
+pub fn add_defaults(trait_def: &mut ItemTrait, defaults: &[TypeParam]) -> syn::Result<()> {
+    for d in defaults {
+        let default = match (&d.default, d.bounds.is_empty()) {
+            (Some(default), true) => default,
+            _ => return Err(syn::Error::new_spanned(d, "expected `Param = Type`")),
+        };
+        let param = trait_def
+            .generics
+            .type_params_mut()
+            .find(|p| p.ident == d.ident)
+            .ok_or_else(|| {
+                syn::Error::new(
+                    d.ident.span(),
+                    format!("there's no type parameter `{}`", d.ident),
+                )
+            })?;
+        param.eq_token = d.eq_token;
+        param.default = Some(default.clone());
+    }
+    Ok(())
+}
+
+/// Make the associated types of the impl the defaults of the ones of the trait, which the impl
+/// still sets explicitly
+pub fn add_type_defaults(trait_def: &mut ItemTrait, item: &ItemImpl) {
+    let types: HashMap<&Ident, &ImplItemType> = item
+        .items
+        .iter()
+        .filter_map(|ii| match ii {
+            ImplItem::Type(it) => Some((&it.ident, it)),
+            _ => None,
+        })
+        .collect();
+    for ti in &mut trait_def.items {
+        if let TraitItem::Type(t) = ti {
+            if let Some(ty) = types.get(&t.ident) {
+                t.default = Some((ty.eq_token, ty.ty.clone()));
+            }
+        }
+    }
+}
+
+/// Move the methods and constants with the given names out of the impl and into the trait as
+/// defaults, with the self type spelled as `Self` if `self_in_defaults` is set
+pub fn move_defaults(
+    trait_def: &mut ItemTrait,
+    item: &mut ItemImpl,
+    names: &HashSet<String>,
+    self_in_defaults: bool,
+) {
+    if names.is_empty() {
+        return;
+    }
+    // the position of each method and constant in the trait
+    let positions: HashMap<Ident, usize> = trait_def
+        .items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, ti)| match ti {
+            TraitItem::Const(c) => Some((c.ident.clone(), idx)),
+            TraitItem::Fn(m) => Some((m.sig.ident.clone(), idx)),
+            _ => None,
+        })
+        .collect();
+    let mut kept = Vec::new();
+    for mut ii in std::mem::take(&mut item.items) {
+        let ident = match &ii {
+            ImplItem::Const(c) => &c.ident,
+            ImplItem::Fn(m) => &m.sig.ident,
+            _ => {
+                kept.push(ii);
+                continue;
+            }
+        };
+        if !names.contains(&ident.to_string()) {
+            kept.push(ii);
+            continue;
+        }
+        let idx = positions.get(ident).copied();
+        if self_in_defaults {
+            self_type_to_self(&mut ii, &item.self_ty);
+        }
+        match (idx.map(|idx| &mut trait_def.items[idx]), ii) {
+            (Some(TraitItem::Fn(tm)), ImplItem::Fn(m)) => {
+                tm.default = Some(m.block);
+                tm.semi_token = None;
+            }
+            (Some(TraitItem::Const(tc)), ImplItem::Const(c)) => {
+                tc.default = Some((c.eq_token, c.expr));
+            }
+            _ => {}
+        }
     }
+    item.items = kept;
+}
+
+/// Order the items of the trait by kind (constants, types, methods and then the rest) and name
+pub fn sort_items(trait_def: &mut ItemTrait) {
+    trait_def.items.sort_by_cached_key(|ti| match ti {
+        TraitItem::Const(c) => (0, c.ident.to_string()),
+        TraitItem::Type(t) => (1, t.ident.to_string()),
+        TraitItem::Fn(m) => (2, m.sig.ident.to_string()),
+        // macros are kept in their original order
+        _ => (3, String::new()),
+    });
 }
@@ -0,0 +1,207 @@
+use super::args::RawArg;
+use super::attr_tokens;
+use super::process_impl::by_value;
+use proc_macro2::Ident;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, ImplItem, LitStr, Token, TraitItem, Type};
+
+/// `#[ext(...)]`, or a namespaced form like `#[ext_trait::ext(...)]` or `#[ext_trait::hide]`
+fn is_ext_attr(attr: &Attribute) -> bool {
+    attr.path().is_ident("ext") || namespaced_key(attr).is_some()
+}
+
+/// The `key` of an `#[ext_trait::key ...]` attribute
+fn namespaced_key(attr: &Attribute) -> Option<&Ident> {
+    let segments = &attr.path().segments;
+    if segments.len() == 2 && segments[0].ident == "ext_trait" {
+        Some(&segments[1].ident)
+    } else {
+        None
+    }
+}
+
+/// The options given by an item attribute
+fn raw_args(attr: &Attribute) -> syn::Result<Vec<RawArg>> {
+    match namespaced_key(attr) {
+        Some(key) if key != "ext" => {
+            // `#[ext_trait::rename = "..."]` is the same as `#[ext(rename = "...")]`
+            let tokens = attr_tokens(attr);
+            syn::parse2(quote!(#key #tokens)).map(|arg| vec![arg])
+        }
+        _ => attr
+            .parse_args_with(Punctuated::<RawArg, Token![,]>::parse_terminated)
+            .map(|p| p.into_iter().collect()),
+    }
+}
+
+/// The arguments of `#[ext(...)]` attributes on individual items of the impl
+#[derive(Default)]
+pub struct ItemArgs {
+    /// Put the item into a separate trait named after the group
+    pub group: Option<LitStr>,
+    /// The name of the item in the trait and the impl, instead of the one that's written
+    pub rename: Option<LitStr>,
+    /// Hide the item from the documentation of the trait
+    pub hide: bool,
+    /// Make the body of the method (or the value of the constant) the default in the trait,
+    /// instead of implementing the item
+    pub default: bool,
+    /// The name of the iterator adapter struct that the method returns, which is generated
+    pub adapter: Option<Ident>,
+    /// The documentation of the item in the trait, instead of the one of the impl
+    pub trait_doc: Option<LitStr>,
+    /// Items that replace this one in the impl for another type of `also_for`
+    pub overrides: Vec<Override>,
+}
+
+/// `for(Type) { items }`
+pub struct Override {
+    pub target: Type,
+    pub items: Vec<ImplItem>,
+}
+
+/// The trait that an item ends up in
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Group {
+    Main,
+    /// Given with `#[ext(group = "...")]`
+    Named(String),
+    /// Methods that take `&mut self`, with `split_mut`
+    Mut,
+    /// Methods that take `self`, with `split_consuming`
+    Consuming,
+}
+
+/// Whether the item is a method that takes `self` by value
+pub fn takes_self(item: &ImplItem) -> bool {
+    match item {
+        ImplItem::Fn(m) => matches!(m.sig.receiver(), Some(r) if by_value(r)),
+        _ => false,
+    }
+}
+
+/// Whether the item is a method that takes `&mut self`
+pub fn takes_mut_self(item: &ImplItem) -> bool {
+    match item {
+        ImplItem::Fn(m) => matches!(
+            m.sig.receiver(),
+            Some(r) if r.reference.is_some() && r.mutability.is_some()
+        ),
+        _ => false,
+    }
+}
+
+impl ItemArgs {
+    fn apply(&mut self, arg: RawArg) -> syn::Result<()> {
+        match arg.key.to_string().as_str() {
+            "group" => self.group = Some(arg.parse_assign()?),
+            "rename" => self.rename = Some(arg.parse_assign()?),
+            "hide" => {
+                arg.expect_flag()?;
+                self.hide = true;
+            }
+            "default" => {
+                arg.expect_flag()?;
+                self.default = true;
+            }
+            "trait_doc" => self.trait_doc = Some(arg.parse_assign()?),
+            "adapter" => self.adapter = Some(arg.parse_assign()?),
+            "for" => {
+                let (target, items) = arg.parse_block()?;
+                self.overrides.push(Override { target, items });
+            }
+            _ => {
+                return Err(syn::Error::new(
+                    arg.key.span(),
+                    format!("unknown ext item option `{}`", arg.key),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    pub fn of(item: &ImplItem) -> syn::Result<Self> {
+        let mut args = ItemArgs::default();
+        for attr in impl_item_attrs(item).iter().filter(|a| is_ext_attr(a)) {
+            for arg in raw_args(attr)? {
+                args.apply(arg)?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// The name of a constant, method or type
+pub fn impl_item_ident(item: &ImplItem) -> Option<&Ident> {
+    match item {
+        ImplItem::Const(c) => Some(&c.ident),
+        ImplItem::Fn(m) => Some(&m.sig.ident),
+        ImplItem::Type(t) => Some(&t.ident),
+        _ => None,
+    }
+}
+
+/// The name of a constant, method or type of a trait
+pub fn trait_item_ident(item: &TraitItem) -> Option<&Ident> {
+    match item {
+        TraitItem::Const(c) => Some(&c.ident),
+        TraitItem::Fn(m) => Some(&m.sig.ident),
+        TraitItem::Type(t) => Some(&t.ident),
+        _ => None,
+    }
+}
+
+/// Apply a `rename`, if there is one
+pub fn rename_impl_item(item: &mut ImplItem, rename: &LitStr) -> syn::Result<()> {
+    let new = rename.parse()?;
+    match item {
+        ImplItem::Const(c) => c.ident = new,
+        ImplItem::Fn(m) => m.sig.ident = new,
+        ImplItem::Type(t) => t.ident = new,
+        _ => {
+            return Err(syn::Error::new(
+                rename.span(),
+                "only constants, methods and types can be renamed",
+            ))
+        }
+    }
+    Ok(())
+}
+
+pub fn impl_item_attrs(item: &ImplItem) -> &[Attribute] {
+    match item {
+        ImplItem::Const(c) => &c.attrs,
+        ImplItem::Fn(m) => &m.attrs,
+        ImplItem::Type(t) => &t.attrs,
+        ImplItem::Macro(m) => &m.attrs,
+        _ => &[],
+    }
+}
+
+pub fn impl_item_attrs_mut(item: &mut ImplItem) -> Option<&mut Vec<Attribute>> {
+    match item {
+        ImplItem::Const(c) => Some(&mut c.attrs),
+        ImplItem::Fn(m) => Some(&mut m.attrs),
+        ImplItem::Type(t) => Some(&mut t.attrs),
+        ImplItem::Macro(m) => Some(&mut m.attrs),
+        _ => None,
+    }
+}
+
+/// Remove the item-level `#[ext(...)]` attributes, which aren't real attributes
+pub fn strip_impl_item(item: &mut ImplItem) {
+    if let Some(attrs) = impl_item_attrs_mut(item) {
+        attrs.retain(|a| !is_ext_attr(a));
+    }
+}
+
+pub fn trait_item_attrs_mut(item: &mut TraitItem) -> Option<&mut Vec<Attribute>> {
+    match item {
+        TraitItem::Const(c) => Some(&mut c.attrs),
+        TraitItem::Fn(m) => Some(&mut m.attrs),
+        TraitItem::Type(t) => Some(&mut t.attrs),
+        TraitItem::Macro(m) => Some(&mut m.attrs),
+        _ => None,
+    }
+}
@@ -10,7 +10,7 @@
 //! ```
 //! use ext_trait::ext;
 //!
-//! // No argument to the macro => A trait name is generated
+//! // No argument to the macro => A trait name is generated (something like `__U8ExtTrait_0123456789abcdef`)
 //! #[ext]
 //! impl u8 {
 //!     fn foo(self) -> u8 { self + 1 }
@@ -87,28 +87,336 @@
 //! assert_eq!(v.second(), Some(&2));
 //! ```
 //!
+//! - Several self types
+//! ```
+//! use ext_trait::ext;
+//!
+//! // the trait is generated from the impl for `u8` and then implemented
+//! // for `u8` and every other signed and unsigned integer type
+//! #[ext(IntExt, for_primitives(signed, unsigned))]
+//! impl u8 {
+//!     fn is_even(self) -> bool { self % 2 == 0 }
+//! }
+//!
+//! // `for_floats` is short for `for_primitives(floats)`
+//! // and `also_for(...)` takes arbitrary types
+//! #[ext(NumExt, for_floats, also_for(u8, u16))]
+//! impl f32 {
+//!     fn halve(self) -> Self { self / (2 as Self) }
+//! }
+//!
+//! assert!(4u8.is_even());
+//! assert!(!(-3i64).is_even());
+//! assert_eq!(3f64.halve(), 1.5);
+//! assert_eq!(3u16.halve(), 1);
+//! ```
+//!
+//! - Tuples
+//! ```
+//! use ext_trait::ext;
+//!
+//! // `Tuple` stands for the tuple type, bounds on it apply to every element.
+//! // Inside `for_tuples!`, `#( ... )sep*` is repeated for every element
+//! // with `Tuple` standing for the element type and `.Tuple` for the field access.
+//! #[ext(TupleExt, tuples(0..=4))]
+//! impl Tuple
+//! where
+//!     Tuple: ToString,
+//! {
+//!     fn strings(&self) -> Vec<String> {
+//!         vec![for_tuples!( #( Tuple::to_string(&self.Tuple) ),* )]
+//!     }
+//! }
+//!
+//! assert_eq!((1, 'a', "b").strings(), ["1", "a", "b"]);
+//! assert!(().strings().is_empty());
+//! ```
+//!
+//! - Splitting into several traits
+//! ```
+//! use ext_trait::ext;
+//!
+//! // items marked with a group end up in their own trait (with its own impl)
+//! #[ext(pub BufExt)]
+//! impl Vec<u8> {
+//!     fn peek_first(&self) -> Option<u8> { self.first().copied() }
+//!
+//!     #[ext(group = "write")]
+//!     fn put(&mut self, b: u8) { self.push(b) }
+//!
+//!     #[ext(group = "write")]
+//!     fn put_twice(&mut self, b: u8) { self.put(b); self.put(b) }
+//! }
+//!
+//! fn fill<B: BufWriteExt>(buf: &mut B) { buf.put_twice(0) }
+//!
+//! let mut v = vec![];
+//! fill(&mut v);
+//! assert_eq!(v.peek_first(), Some(0));
+//! assert_eq!(v.len(), 2);
+//! ```
+//!
+//! - Naming auto-generated traits
+//! ```
+//! use ext_trait::{ext, ext_trait_name};
+//!
+//! // with an `id`, the generated name only depends on the id, not on the contents of the impl
+//! #[ext(id = "u8-double")]
+//! impl u8 {
+//!     fn double(self) -> u8 { self * 2 }
+//! }
+//!
+//! // `ext_trait_name!` expands to a `use` of the generated trait under a name of your choice
+//! ext_trait_name!(U8Double = "u8-double");
+//!
+//! fn double_all<T: U8Double + Copy>(v: &[T]) -> Vec<u8> {
+//!     v.iter().map(|x| x.double()).collect()
+//! }
+//!
+//! assert_eq!(double_all(&[1u8, 2]), vec![2, 4]);
+//!
+//! // `alias` re-exports the trait under a name of your choice directly
+//! #[ext(alias = BytesExt)]
+//! impl Vec<u8> {
+//!     fn total(&self) -> u32 { self.iter().map(|&b| u32::from(b)).sum() }
+//! }
+//!
+//! fn total<B: BytesExt>(b: &B) -> u32 { b.total() }
+//! assert_eq!(total(&vec![1, 2]), 3);
+//! ```
+//!
+//! - Whole modules
+//! ```
+//! mod exts {
+//!     use ext_trait::{ext, ext_mod};
+//!
+//!     // the arguments are shared by every inherent impl in the module:
+//!     // `{type}` is replaced by the self type and `sealed` forbids implementing the trait elsewhere
+//!     #[ext_mod(pub, name = "{type}Ext", sealed)]
+//!     pub mod ints {
+//!         impl u8 {
+//!             pub fn double(self) -> u8 { self * 2 }
+//!         }
+//!
+//!         impl Vec<u16> {
+//!             pub fn total(&self) -> u16 { self.iter().sum() }
+//!         }
+//!
+//!         // individual impls can still override the module's settings
+//!         #[ext(OtherName)]
+//!         impl u32 {
+//!             pub fn triple(self) -> u32 { self * 3 }
+//!         }
+//!
+//!         // and later impls can add to the trait of an earlier one (with the same self type)
+//!         #[ext(append_to = OtherName)]
+//!         impl u32 {
+//!             pub fn quadruple(self) -> u32 { self * 4 }
+//!         }
+//!
+//!         pub fn use_other_name(x: u32) -> u32 { x.triple() + x.quadruple() }
+//!     }
+//! }
+//!
+//! use exts::ints::{U8Ext, VecU16Ext};
+//! assert_eq!(3u8.double(), 6);
+//! assert_eq!(vec![1u16, 2].total(), 3);
+//! assert_eq!(exts::ints::use_other_name(1), 7);
+//! ```
+//!
+//! Options shared by the whole crate can be set in its `Cargo.toml`, as a (single-line) string
+//! with the same syntax as the arguments of `#[ext(...)]`:
+//! ```toml
+//! [package.metadata.ext_trait]
+//! defaults = 'pub(crate), name = "{type}Ext", sealed'
+//! ```
+//! They apply to every `#[ext]`, `#[ext_mod]` and `ext_block!` of the crate, and options given to
+//! a block (or module) take precedence, the same as with `#[ext_mod]`. Only the options that
+//! `#[ext_mod]` passes on to its blocks can be used.
+//!
+//! With `#[ext_decl]` instead of `#[ext]`, the crate also exports a macro that other crates can
+//! use to implement the trait for their own types.
+//!
+//! - Preludes
+//! ```
+//! mod bytes {
+//!     use ext_trait::ext;
+//!
+//!     // registered traits must be visible to the prelude
+//!     #[ext(pub, prelude)]
+//!     impl u8 {
+//!         pub fn is_nul(self) -> bool { self == 0 }
+//!     }
+//! }
+//!
+//! mod more {
+//!     use ext_trait::ext_mod;
+//!
+//!     // a single `#[ext(prelude)]` can be used per module, `ext_mod` can register any number
+//!     #[ext_mod(pub, prelude)]
+//!     pub mod exts {
+//!         impl u16 {
+//!             pub fn lo(self) -> u8 { self as u8 }
+//!         }
+//!
+//!         impl u32 {
+//!             pub fn lo(self) -> u16 { self as u16 }
+//!         }
+//!     }
+//! }
+//!
+//! ext_trait::ext_prelude!(pub mod prelude { crate::bytes, crate::more::exts });
+//!
+//! fn main() {
+//!     use prelude::*;
+//!     assert!(0x1_0000u32.lo().lo().is_nul());
+//! }
+//! ```
+//!
+//! - Combining traits into one bound
+//! ```
+//! use ext_trait::{ext, ext_combine};
+//!
+//! #[ext(Halve)]
+//! impl i32 {
+//!     fn halve(self) -> i32 { self / 2 }
+//! }
+//!
+//! #[ext(Negate)]
+//! impl i32 {
+//!     fn negate(self) -> i32 { -self }
+//! }
+//!
+//! ext_combine!(trait Arith: Halve + Negate + Copy;);
+//!
+//! fn both<T: Arith>(x: T) -> (i32, i32) {
+//!     (x.halve(), x.negate())
+//! }
+//!
+//! assert_eq!(both(4), (2, -4));
+//! ```
+//!
+//! - Conditional compilation
+//! ```
+//! use ext_trait::ext;
+//!
+//! // the `#[cfg]` is put on the trait as well as on the impl
+//! #[ext(StrExt, cfg(not(feature = "some-feature")))]
+//! impl str {
+//!     fn shout(&self) -> String { self.to_uppercase() }
+//! }
+//!
+//! #[ext(StrExt, cfg(feature = "some-feature"), doc_cfg)]
+//! impl str {
+//!     fn shout(&self) -> String { format!("{}!", self.to_uppercase()) }
+//! }
+//!
+//! assert_eq!("hi".shout(), "HI");
+//! ```
+//!
+//! With `doc_cfg`, the `#[cfg]`s of the trait and its items are also shown in the documentation
+//! when building with `--cfg docsrs` (and `#![feature(doc_cfg)]` enabled for that case).
+//!
+//! - Mocking (requires [`mockall`](https://docs.rs/mockall) as a dev-dependency)
+//! ```
+//! use ext_trait::ext;
+//!
+//! pub struct Db;
+//!
+//! // in test builds, this also generates `MockDbExt`
+//! #[ext(pub DbExt, mock)]
+//! impl Db {
+//!     type Key = u32;
+//!
+//!     fn lookup(&self, key: u32) -> Option<String> { None }
+//! }
+//!
+//! fn describe(db: &impl DbExt) -> String {
+//!     db.lookup(1).unwrap_or_default()
+//! }
+//! # assert_eq!(describe(&Db), "");
+//! ```
+//!
+//! With [`faux`](https://docs.rs/faux), which mocks structs instead of traits, the struct is
+//! marked `#[cfg_attr(test, faux::create)]` and `#[ext(faux)]` takes care of the impl:
+//! ```
+//! use ext_trait::ext;
+//!
+//! #[cfg_attr(test, faux::create)]
+//! pub struct Client;
+//!
+//! #[ext(pub ClientExt, faux)]
+//! impl Client {
+//!     fn fetch(&self, id: u32) -> String { id.to_string() }
+//! }
+//! # assert_eq!(Client.fetch(1), "1");
+//! ```
+//!
+//! Built-in attributes on the impl and its items that mean the same on the trait (`doc`, `cfg`,
+//! lints, `must_use`, `deprecated`, `track_caller` and tool attributes like `rustfmt::skip`)
+//! are copied to the trait, except for the ones listed in `impl_only(...)`; `must_use` and
+//! `deprecated` are then removed from the impl, where they don't have an effect. All other
+//! attributes, like attribute macros and helper attributes (e.g. those of `wasm_bindgen` or
+//! `pyo3`), as well as `inline` and `cold`, stay on the impl only, so that they are expanded
+//! exactly once. A `cfg_attr` is copied if all the attributes in it are, and otherwise only with
+//! the ones that are. Attribute macros that have to see the trait as well, like `async_trait`, can
+//! be copied with `copy_attrs(...)`.
+//!
+//! `cfg`s (including those in `cfg_attr`s) are always copied, even with `impl_only(cfg)`, so that
+//! the items of the trait and the impl exist under the same conditions. Code generated for an item
+//! (like the methods of a `newtype` or the functions of `also_fns`) gets its `cfg`s as well.
+//!
+//! With `warn_shadowing`, methods that are named like methods of prominent std traits get a
+//! warning (here turned into an error):
+//! ```compile_fail
+//! #![deny(deprecated)]
+//! use ext_trait::ext;
+//!
+//! #[ext(warn_shadowing)]
+//! impl str {
+//!     fn into_iter(&self) -> std::str::Chars { self.chars() }
+//! }
+//! ```
+//!
+//! The expansion only depends on the macro input, on where it is (the file relative to the crate,
+//! the line and the column of the impl) and on how many identical blocks in the same place came
+//! before it: it is the same on every machine, so tools like `cargo-semver-checks`, reproducible
+//! builds and compilation caches always see identical generated items (including the generated
+//! trait names). Compilers before Rust 1.88 don't tell proc macros where their input is, so there,
+//! identical blocks are only told apart by their order.
+//!
+//! Projects that want every trait to have a name they chose can turn blocks without a name (or
+//! a name template) into errors by enabling the `require_names` feature. Names made from an `id`
+//! count as missing, since they are hashed as well.
+//!
 //! # Comparison to similar crates
 //! - [`easy_ext`](https://crates.io/crates/easy-ext) only supports methods and constants, not types and macro invokations; also, the implementation is different
 //!     - to be fair, macro invokations are impossible to fully support with this pattern (as far as I can see)
 //!
 //! # Quirks
 //! - The generated trait doesn't retain implicit trait bounds, specifically impls for (implicitly) `Sized` types are not
-//!    converted into traits that require `Self: Sized`
-//!     - Mostly, this leads to no problem since the type is often either explicitly `?Sized` or
-//!         the ext trait only gets implemented for sized types
-//!     - In case of any problems, just add a `where Self: Sized` bound to the impl and all is good (see example below)
+//!   converted into traits that require `Self: Sized`
+//!     - Methods whose signatures need `Self: Sized` (like `fn foo(self) -> Vec<Self>`) get it as a `where` clause
+//!       automatically, unless `no_auto_sized` is given (see example below)
+//!     - In case of any other problems, just add a `where Self: Sized` bound to the impl and all is good
 //! - Because the random trait names are created using hashing of the input, there is a tiny chance of a collision.
 //!     - In that case, you can define a macro that expands to nothing and insert it into the impl. That should shake up the hash a bit.
+//!     - Identical blocks get different names: from their location or, for blocks in the same place (like the body of a
+//!       `macro_rules!` macro that is invoked several times), from the order in which they are expanded.
+//! - The generated names have `Span::mixed_site()` hygiene, the same as names that a `macro_rules!` macro makes up.
+//!     - Under it, items (unlike local variables, labels and `$crate`) aren't hygienic, so the traits can still be named
+//!       (and clash) in user code; truly anonymous names would need `Span::def_site()`, which is unstable.
+//!     - That they stay nameable is also what makes `ext_trait_name!` work.
 //!
 //! ## Example: Fixing `Sized`-Issue
-//! The following code will not compile:
+//! The following code will not compile without the automatic `where Self: Sized`:
 //! ```compile_fail
 //! use std::marker::PhantomData;
 //! use ext_trait::ext;
 //!
 //! pub struct AssertSized<T>(PhantomData<T>);
 //!
-//! #[ext]
+//! #[ext(no_auto_sized)]
 //! impl<T> T {
 //!     fn foo(self) -> AssertSized<Self> { AssertSized(PhantomData) }
 //! }
@@ -143,30 +451,199 @@
 //! ```
 //!
 //! Note also that something like `#[ext] impl<T> [T] where Self: Sized { … }` will compile, but won't do anything since `[T]` is never `Sized`.
+//!
+//! For self types that are never `Sized` (`str`, slices and trait objects), methods that take or
+//! return `self` by value are rejected right away:
+//! ```compile_fail
+//! use ext_trait::ext;
+//!
+//! #[ext]
+//! impl str {
+//!     fn consume(self) {}
+//! }
+//! ```
 
+use args::{DocLinks, ExtArgs};
+use item_args::{Group, ItemArgs};
 use proc_macro::TokenStream;
-use proc_macro2::Span;
-use quote::quote;
-use std::collections::hash_map::DefaultHasher;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, ToTokens};
+use std::collections::{HashMap, HashSet};
 use std::hash::Hasher;
-use syn::parse::{Parse, ParseStream};
+use syn::parse::{Parse, ParseStream, Parser};
 use syn::punctuated::Punctuated;
 use syn::{
-    parse_macro_input, Ident, ItemImpl, Path, PathArguments, PathSegment, Token, Visibility,
+    parse_macro_input, Attribute, Generics, Ident, ImplItem, Item, ItemImpl, ItemMod, ItemTrait,
+    LitStr, MacroDelimiter, Meta, Path, PathArguments, PathSegment, Token, TraitItem, Type,
+    TypeParamBound, Visibility,
 };
 // for some reason IntelliJ doesn't detect the other Token import so this is a quick fix
 #[allow(unused_imports)]
 use syn::token::Token;
 
+mod adapters;
+mod args;
+mod config;
+mod debug;
+mod decl;
+mod docs;
+mod forward;
 mod impl_to_trait;
+mod item_args;
+mod naming;
 mod process_impl;
+mod routing;
+mod shadowing;
+mod test_doubles;
+mod tuples;
 
-fn hash(input: &TokenStream) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    hasher.write(input.to_string().as_bytes());
+/// 64-bit FNV-1a, which (unlike `DefaultHasher`) is guaranteed to be the same on every toolchain
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+fn hash(input: &str) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write(input.as_bytes());
     hasher.finish()
 }
 
+fn hash_token_stream(hasher: &mut StableHasher, input: &TokenStream2) {
+    use proc_macro2::{Delimiter, TokenTree};
+
+    for tt in input.clone() {
+        match tt {
+            TokenTree::Group(g) => {
+                let (open, close) = match g.delimiter() {
+                    Delimiter::Parenthesis => (b'(', b')'),
+                    Delimiter::Brace => (b'{', b'}'),
+                    Delimiter::Bracket => (b'[', b']'),
+                    Delimiter::None => (0, 0),
+                };
+                hasher.write_u8(open);
+                hash_token_stream(hasher, &g.stream());
+                hasher.write_u8(close);
+            }
+            TokenTree::Ident(i) => hasher.write(i.to_string().as_bytes()),
+            TokenTree::Punct(p) => hasher.write_u32(p.as_char() as u32),
+            TokenTree::Literal(l) => hasher.write(l.to_string().as_bytes()),
+        }
+        // separate the tokens so that e.g. `a b` and `ab` hash differently
+        hasher.write_u8(0xff);
+    }
+}
+
+/// The file that `span` is in, relative to the crate (if it's in it), so that it's the same on
+/// every machine
+///
+/// Compilers before 1.88 don't tell where spans are, so it's always the same there.
+fn relative_file(span: proc_macro2::Span) -> String {
+    let file = span.file();
+    let dir = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+    std::path::Path::new(&file)
+        .strip_prefix(&dir)
+        .map(|relative| relative.to_string_lossy().into_owned())
+        .unwrap_or(file)
+}
+
+/// Where `span` is, as `file:line:column` (see `relative_file`)
+fn location(span: proc_macro2::Span) -> String {
+    let start = span.start();
+    format!("{}:{}:{}", relative_file(span), start.line, start.column)
+}
+
+thread_local! {
+    /// How many blocks with each hash have been named so far, per crate
+    ///
+    /// The compiler loads proc macros once per crate that it compiles and expands all macros of
+    /// the crate on the same thread, but other hosts (like rust-analyzer) may keep them loaded for
+    /// several crates, hence the crate in the key.
+    static SEEN: std::cell::RefCell<HashMap<(String, u64), u64>> = Default::default();
+}
+
+/// The hash for the auto-generated name of a block, which also depends on where the block is, so
+/// that identical blocks get different names
+///
+/// The tokens are hashed one by one instead of stringifying the whole input first.
+///
+/// Blocks from the body of a `macro_rules!` macro have the same tokens and location in every
+/// invocation of the macro, so the second, third, ... block with the same hash in a crate also
+/// gets its number mixed in. The compiler expands macros in a fixed order, so the numbers (and
+/// names) are the same in every build.
+fn hash_input(input: &TokenStream2, item: &ItemImpl) -> u64 {
+    let mut hasher = StableHasher::new();
+    hash_token_stream(&mut hasher, input);
+    hasher.write(location(item.impl_token.span).as_bytes());
+    let hash = hasher.finish();
+
+    let krate = format!(
+        "{}/{}",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default(),
+        std::env::var("CARGO_CRATE_NAME").unwrap_or_default()
+    );
+    let count = SEEN.with(|seen| {
+        let mut seen = seen.borrow_mut();
+        let count = seen.entry((krate, hash)).or_insert(0);
+        *count += 1;
+        *count
+    });
+    if count == 1 {
+        hash
+    } else {
+        hasher.write_u64(count);
+        hasher.finish()
+    }
+}
+
+/// Compare syntax trees by their tokens (which is what `extra-traits` would do, but cheaper to build)
+///
+/// Invisible groups, which e.g. `$t:ty` metavariables of `macro_rules!` macros are wrapped in,
+/// don't count, so that `$t` is equal to the type it stands for.
+fn tokens_eq<T: ToTokens>(a: &T, b: &T) -> bool {
+    tokens_key(a) == tokens_key(b)
+}
+
+/// The tokens as `tokens_eq` compares them, for comparing one syntax tree with many others
+/// without spelling it out every time
+fn tokens_key<T: ToTokens>(t: &T) -> String {
+    without_invisible_groups(t.to_token_stream()).to_string()
+}
+
+/// The tokens with every `None`-delimited group replaced by its contents
+fn without_invisible_groups(ts: TokenStream2) -> TokenStream2 {
+    use proc_macro2::{Delimiter, Group, TokenTree};
+    ts.into_iter()
+        .flat_map(|tt| match tt {
+            TokenTree::Group(g) if g.delimiter() == Delimiter::None => {
+                without_invisible_groups(g.stream())
+            }
+            TokenTree::Group(g) => {
+                let mut new = Group::new(g.delimiter(), without_invisible_groups(g.stream()));
+                new.set_span(g.span());
+                TokenTree::Group(new).into()
+            }
+            tt => tt.into(),
+        })
+        .collect()
+}
+
 fn ident_to_path(ident: Ident) -> Path {
     let mut segments = Punctuated::new();
     segments.push(PathSegment {
@@ -180,45 +657,1693 @@ fn ident_to_path(ident: Ident) -> Path {
     }
 }
 
-struct ExtArgs {
-    pub vis: Visibility,
-    ident: Option<Ident>,
+/// Generate the trait definitions and impls, one for each group of items
+///
+/// Returns the names of all generated traits along with the code.
+fn expand_groups(
+    args: &ExtArgs,
+    mut item: ItemImpl,
+    name: Ident,
+) -> syn::Result<(TokenStream2, Vec<Ident>)> {
+    let mut groups: Vec<(Group, Vec<ImplItem>)> = vec![(Group::Main, Vec::new())];
+    // the index of each group in `groups`, which keeps them in order of appearance
+    let mut group_idx = HashMap::new();
+    group_idx.insert(Group::Main, 0);
+    for mut ii in std::mem::take(&mut item.items) {
+        let item_args = ItemArgs::of(&ii)?;
+        if let Some(rename) = &item_args.rename {
+            item_args::rename_impl_item(&mut ii, rename)?;
+        }
+        if item_args.hide {
+            if let Some(attrs) = item_args::impl_item_attrs_mut(&mut ii) {
+                attrs.push(syn::parse_quote!(#[doc(hidden)]));
+            }
+        }
+        let group = match item_args.group {
+            Some(group) => Group::Named(group.value()),
+            None if args.split_mut && item_args::takes_mut_self(&ii) => Group::Mut,
+            None if args.split_consuming && item_args::takes_self(&ii) => Group::Consuming,
+            None => Group::Main,
+        };
+        let idx = *group_idx.entry(group.clone()).or_insert_with(|| {
+            groups.push((group, Vec::new()));
+            groups.len() - 1
+        });
+        groups[idx].1.push(ii);
+    }
+
+    let only_main = groups.len() == 1;
+    if !only_main && args.decl {
+        return Err(syn::Error::new(
+            item.impl_token.span,
+            "`ext_decl` doesn't support putting items into separate traits",
+        ));
+    }
+    if !only_main && !args.other_blocks.is_empty() {
+        return Err(syn::Error::new(
+            item.impl_token.span,
+            "`ext_block!` doesn't support putting items into separate traits",
+        ));
+    }
+    let mut fns = TokenStream2::new();
+    let mut traits = TokenStream2::new();
+    let mut out = TokenStream2::new();
+    let mut names = Vec::new();
+    for (group, items) in groups {
+        // the main trait is left out if all items are in other groups
+        if items.is_empty() && !only_main {
+            continue;
+        }
+        let name = match &group {
+            Group::Main => name.clone(),
+            Group::Named(group) => naming::group_ident(&name, group),
+            Group::Mut => naming::mut_ident(&name),
+            Group::Consuming => naming::consuming_ident(&name),
+        };
+        let main = group == Group::Main;
+        let mut group_item = item.clone();
+        group_item.items = items;
+        let (mut trait_def, rest) = expand(args, group_item, name.clone())?;
+        if group == Group::Consuming {
+            trait_def
+                .generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(Self: Sized));
+        }
+        if let (true, Some(mock)) = (main, &args.gen_mock) {
+            out.extend(test_doubles::gen_mock(args, &trait_def, mock)?);
+        }
+        if let (true, Some(spy)) = (main, &args.gen_spy) {
+            out.extend(test_doubles::gen_spy(args, &trait_def, &item, spy)?);
+        }
+        if let (true, Some(newtype)) = (main, &args.newtype) {
+            out.extend(forward::newtype(args, &trait_def, &item, newtype)?);
+        }
+        if let (true, Some(dummies)) = (main, &args.dummy_impl) {
+            out.extend(test_doubles::dummy_impls(args, &trait_def, dummies)?);
+        }
+        if main && !args.assert_impl_for.is_empty() {
+            out.extend(assert_impls(args, &trait_def)?);
+        }
+        if args.also_fns.is_some() {
+            fns.extend(forward::free_fns(args, &trait_def, &item.self_ty)?);
+        }
+        if args.delegatable {
+            traits.extend(forward::delegate_macro(args, &trait_def)?);
+        }
+        if args.decl && main {
+            out.extend(decl::capsule(args, &trait_def)?);
+        }
+        traits.extend(hide_trait(args, trait_def));
+        out.extend(rest);
+        names.push(name);
+    }
+    match &args.also_fns {
+        Some(Some(module)) => {
+            let vis = args.vis();
+            let cfg = args.cfg_attrs();
+            out.extend(quote! {
+                #(#cfg)*
+                #vis mod #module {
+                    #[allow(unused_imports)]
+                    use super::*;
+                    #fns
+                }
+            });
+        }
+        Some(None) => out.extend(fns),
+        None => {}
+    }
+    let mut out = wrap_in_module(args, traits, out);
+
+    if let (Some((vis, alias)), true) = (&args.alias, names.contains(&name)) {
+        let attrs = args.reexport_attrs();
+        let path = args.trait_path(&name);
+        out.extend(quote!(#(#attrs)* #vis use #path as #alias;));
+    }
+
+    debug::dump(args.debug_dump, &name, item.impl_token.span, &out)?;
+    out.extend(debug::tracking());
+    Ok((out, names))
+}
+
+/// With `hidden_module`, put the trait into a hidden module and re-export it from there
+fn hide_trait(args: &ExtArgs, mut trait_def: ItemTrait) -> TokenStream2 {
+    let module = match args.hidden_module(&trait_def.ident) {
+        Some(module) => module,
+        // with a module, only the outermost one depends on `pub_if`
+        None if args.module.is_empty() => {
+            return with_vis(args, |vis| {
+                trait_def.vis = vis.clone();
+                trait_def.to_token_stream()
+            })
+        }
+        None => return trait_def.into_token_stream(),
+    };
+    let name = &trait_def.ident;
+    let cfg = args.cfg_attrs();
+    let reexport = args.reexport_attrs();
+    let vis = std::mem::replace(&mut trait_def.vis, syn::parse_quote!(pub));
+    let reexport = if args.module.is_empty() {
+        with_vis(
+            args,
+            |vis| quote!(#(#reexport)* #vis use self::#module::#name;),
+        )
+    } else {
+        quote!(#(#reexport)* #vis use self::#module::#name;)
+    };
+    quote! {
+        #(#cfg)*
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        mod #module {
+            #[allow(unused_imports)]
+            use super::*;
+            #trait_def
+        }
+        #reexport
+    }
 }
 
-impl ExtArgs {
-    pub fn trait_ident(&self, input_hash: u64) -> Ident {
-        self.ident
-            .clone()
-            .unwrap_or_else(|| Ident::new(&format!("__ExtTrait{}", input_hash), Span::call_site()))
+/// The item with the given visibility or, with `pub_if(predicate)`, twice: `pub` if the predicate
+/// holds and `pub(crate)` otherwise
+fn with_vis(args: &ExtArgs, mut item: impl FnMut(&Visibility) -> TokenStream2) -> TokenStream2 {
+    match &args.pub_if {
+        Some(predicate) => {
+            let public = item(&syn::parse_quote!(pub));
+            let private = item(&syn::parse_quote!(pub(crate)));
+            quote! {
+                #[cfg(#predicate)]
+                #public
+                #[cfg(not(#predicate))]
+                #private
+            }
+        }
+        None => item(&args.vis()),
     }
 }
 
-impl Parse for ExtArgs {
-    fn parse(input: ParseStream) -> syn::Result<Self> {
-        Ok(ExtArgs {
-            vis: input.parse()?,
-            ident: input.parse()?,
+/// Compile-time checks that the types of `assert_impl_for` implement the trait
+fn assert_impls(args: &ExtArgs, trait_def: &ItemTrait) -> syn::Result<TokenStream2> {
+    if let Some(param) = trait_def.generics.const_params().next() {
+        return Err(syn::Error::new(
+            param.ident.span(),
+            "`assert_impl_for` doesn't support const parameters",
+        ));
+    }
+    let lifetimes = trait_def.generics.lifetimes().map(|l| &l.lifetime);
+    let params: Vec<_> = trait_def.generics.type_params().map(|p| &p.ident).collect();
+    let (_, ty_generics, _) = trait_def.generics.split_for_impl();
+    let trait_path = args.trait_path(&trait_def.ident);
+    // predicates on `Self` are implied by the trait bound, the others aren't
+    let predicates = trait_def
+        .generics
+        .where_clause
+        .iter()
+        .flat_map(|c| &c.predicates)
+        .filter(|p| !process_impl::idents(p).contains("Self"));
+    let inferred = params.iter().map(|_| quote!(_));
+    let checks = args.assert_impl_for.iter().map(|ty| {
+        let inferred = inferred.clone();
+        quote!(assert_impl::<#ty, #(#inferred),*>();)
+    });
+    let cfg = args.cfg_attrs();
+    Ok(quote! {
+        #(#cfg)*
+        const _: fn() = || {
+            fn assert_impl<#(#lifetimes,)* __Implementor: ?Sized, #(#params),*>()
+            where
+                __Implementor: #trait_path #ty_generics,
+                #(#predicates,)*
+            {
+            }
+            #(#checks)*
+        };
+    })
+}
+
+/// The `clone_trait_object!` invocation that implements `Clone` for boxed trait objects
+fn clone_trait_object(args: &ExtArgs, trait_def: &ItemTrait) -> TokenStream2 {
+    let trait_path = args.trait_path(&trait_def.ident);
+    let generics = &trait_def.generics;
+    let (_, ty_generics, _) = generics.split_for_impl();
+    // predicates on `Self` don't make sense for `Box<dyn Trait>`
+    let predicates: Vec<_> = generics
+        .where_clause
+        .iter()
+        .flat_map(|c| &c.predicates)
+        .filter(|p| !process_impl::idents(p).contains("Self"))
+        .collect();
+    let where_clause = if predicates.is_empty() {
+        TokenStream2::new()
+    } else {
+        quote!(where #(#predicates),*)
+    };
+    let params = if generics.params.is_empty() {
+        TokenStream2::new()
+    } else {
+        let params = generics.params.iter();
+        quote!(<#(#params),*>)
+    };
+    let cfg = args.cfg_attrs();
+    quote! {
+        #(#cfg)*
+        dyn_clone::clone_trait_object!(#params #trait_path #ty_generics #where_clause);
+    }
+}
+
+/// The `impl_downcast!` invocation that adds the downcasting methods to the trait objects
+fn impl_downcast(args: &ExtArgs, trait_def: &ItemTrait) -> syn::Result<TokenStream2> {
+    let generics = &trait_def.generics;
+    if let Some(param) = generics.lifetimes().next() {
+        return Err(syn::Error::new_spanned(
+            param,
+            "`downcast` doesn't support lifetime parameters",
+        ));
+    }
+    if let Some(param) = generics.const_params().next() {
+        return Err(syn::Error::new(
+            param.ident.span(),
+            "`downcast` doesn't support const parameters",
+        ));
+    }
+    let ident = &trait_def.ident;
+    let params: Vec<_> = generics.type_params().map(|p| &p.ident).collect();
+    let params = if params.is_empty() {
+        TokenStream2::new()
+    } else {
+        quote!(<#(#params),*>)
+    };
+    let assoc: Vec<_> = trait_def
+        .items
+        .iter()
+        .filter_map(|ti| match ti {
+            TraitItem::Type(t) => Some(&t.ident),
+            _ => None,
         })
+        .collect();
+    let assoc = match assoc[..] {
+        [] => TokenStream2::new(),
+        [assoc] => quote!(assoc #assoc),
+        [_, second, ..] => {
+            return Err(syn::Error::new(
+                second.span(),
+                "`downcast` supports at most one associated type",
+            ))
+        }
+    };
+    // predicates on `Self` don't make sense for `dyn Trait`
+    let predicates: Vec<_> = generics
+        .where_clause
+        .iter()
+        .flat_map(|c| &c.predicates)
+        .filter(|p| !process_impl::idents(p).contains("Self"))
+        .collect();
+    let where_clause = if predicates.is_empty() {
+        TokenStream2::new()
+    } else {
+        quote!(where #(#predicates),*)
+    };
+    // `impl_downcast!` only takes the name of the trait, not a path
+    let import = if args.module.is_empty() {
+        TokenStream2::new()
+    } else {
+        let trait_path = args.trait_path(ident);
+        quote!(use #trait_path;)
+    };
+    let cfg = args.cfg_attrs();
+    Ok(quote! {
+        #(#cfg)*
+        const _: () = {
+            #import
+            downcast_rs::impl_downcast!(#ident #params #assoc #where_clause);
+        };
+    })
+}
+
+/// Put the trait definitions into the module given with the name (if any) and append the rest
+fn wrap_in_module(args: &ExtArgs, traits: TokenStream2, rest: TokenStream2) -> TokenStream2 {
+    let vis = args.vis();
+    let mut attrs = args.cfg_attrs();
+    if args.allow_missing_docs {
+        attrs.push(syn::parse_quote!(#[allow(missing_docs)]));
     }
+    let wrap = |vis: &Visibility, module: &Ident, inner: &TokenStream2| {
+        quote! {
+            #(#attrs)*
+            #vis mod #module {
+                #[allow(unused_imports)]
+                use super::*;
+                #inner
+            }
+        }
+    };
+    let (outermost, inner) = match args.module.split_first() {
+        Some(split) => split,
+        None => return quote!(#traits #rest),
+    };
+    let traits = inner
+        .iter()
+        .rev()
+        .fold(traits, |inner, module| wrap(&vis, module, &inner));
+    let traits = with_vis(args, |vis| wrap(vis, outermost, &traits));
+    quote!(#traits #rest)
 }
 
+/// Turn a block after the first one of an `ext_block!` into an impl of the trait, the same way
+/// as the first one
+fn other_block(args: &ExtArgs, mut item: ItemImpl, name: &Ident) -> syn::Result<ItemImpl> {
+    process_impl::unwrap_type_groups(&mut item);
+    item.items.iter_mut().for_each(item_args::strip_impl_item);
+    process_impl::check_unsized(&item)?;
+    if let Some(msrv) = args.msrv {
+        process_impl::apply_msrv(&mut item, msrv)?;
+    }
+    process_impl::move_bounds_to_where_clause(&mut item);
+    process_impl::make_trait_impl(&mut item, args.trait_path(name));
+    if !args.split_self_predicates {
+        process_impl::copy_appropriate_where_clause_type_from_and_to_self(&mut item);
+    }
+    if !args.no_auto_sized {
+        process_impl::add_sized_bounds(&mut item, &HashSet::new());
+    }
+    if args.const_trait {
+        process_impl::strip_constness(&mut item)?;
+    }
+    process_impl::resolve_self_projections(&mut item);
+    Ok(item)
+}
+
+/// The items of a `for(Type) { ... }`, prepared the same way as the ones of `other`, the impl for
+/// `Type` that they go into
+fn override_items(
+    args: &ExtArgs,
+    other: &ItemImpl,
+    items: &[ImplItem],
+    defaulted: &HashSet<String>,
+) -> syn::Result<Vec<ImplItem>> {
+    let mut other = other.clone();
+    other.items = items.to_vec();
+    process_impl::unwrap_type_groups(&mut other);
+    other.items.iter_mut().for_each(item_args::strip_impl_item);
+    process_impl::check_unsized(&other)?;
+    if let Some(msrv) = args.msrv {
+        process_impl::apply_msrv(&mut other, msrv)?;
+    }
+    process_impl::strip_visibility(&mut other.items);
+    if !args.no_auto_sized {
+        process_impl::add_sized_bounds(&mut other, defaulted);
+    }
+    if args.const_trait {
+        process_impl::strip_constness(&mut other)?;
+    }
+    Ok(other.items)
+}
+
+/// Generate the trait definition and (separately) everything else for an inherent impl
+fn expand(
+    args: &ExtArgs,
+    mut item: ItemImpl,
+    name: Ident,
+) -> syn::Result<(ItemTrait, TokenStream2)> {
+    process_impl::unwrap_type_groups(&mut item);
+    let mut defaulted = HashSet::new();
+    // the name of the replaced item along with each `for(Type) { ... }`
+    let mut overrides = Vec::new();
+    let mut trait_docs = Vec::new();
+    let mut adapters = Vec::new();
+    for ii in &item.items {
+        let item_args = ItemArgs::of(ii)?;
+        if let Some(adapter) = item_args.adapter {
+            match ii {
+                ImplItem::Fn(m) => adapters.push((m.clone(), adapter)),
+                _ => {
+                    return Err(syn::Error::new(
+                        adapter.span(),
+                        "only methods can return adapters",
+                    ))
+                }
+            }
+        }
+        if let Some(doc) = item_args.trait_doc {
+            let ident = item_args::impl_item_ident(ii).ok_or_else(|| {
+                syn::Error::new(
+                    doc.span(),
+                    "only constants, methods and types have trait docs",
+                )
+            })?;
+            trait_docs.push((ident.clone(), doc));
+        }
+        if !item_args.overrides.is_empty() {
+            let ident = item_args::impl_item_ident(ii).ok_or_else(|| {
+                syn::Error::new_spanned(ii, "only constants, methods and types can be overridden")
+            })?;
+            for o in item_args.overrides {
+                overrides.push((ident.clone(), o));
+            }
+        }
+        if !item_args.default {
+            continue;
+        }
+        match ii {
+            ImplItem::Const(c) => defaulted.insert(c.ident.to_string()),
+            ImplItem::Fn(m) => defaulted.insert(m.sig.ident.to_string()),
+            ImplItem::Type(t) => {
+                return Err(syn::Error::new(
+                    t.ident.span(),
+                    "use `type_defaults` to make associated types defaults",
+                ))
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ii,
+                    "only methods and constants can be defaults",
+                ))
+            }
+        };
+    }
+    item.items.iter_mut().for_each(item_args::strip_impl_item);
+    let inherent = match (args.also_inherent, &args.tuples) {
+        (false, _) => None,
+        (true, None) => Some(item.clone()),
+        (true, Some(_)) => {
+            return Err(syn::Error::new(
+                item.impl_token.span,
+                "`also_inherent` can't be combined with `tuples`",
+            ))
+        }
+    };
+
+    let element_bounds = if args.tuples.is_some() {
+        tuples::take_element_bounds(&mut item)
+    } else {
+        Vec::new()
+    };
+    process_impl::check_unsized(&item)?;
+    let async_trait = match args.msrv {
+        Some(msrv) => process_impl::apply_msrv(&mut item, msrv)?,
+        None => false,
+    };
+    process_impl::move_bounds_to_where_clause(&mut item);
+
+    process_impl::make_trait_impl(&mut item, args.trait_path(&name));
+    if args.tuples.is_none() && !args.split_self_predicates {
+        // with tuples, the copies of `Self` predicates would refer to the placeholder, which
+        // doesn't exist
+        process_impl::copy_appropriate_where_clause_type_from_and_to_self(&mut item);
+    }
+    if !args.no_auto_sized {
+        process_impl::add_sized_bounds(&mut item, &defaulted);
+    }
+
+    let mut extra = TokenStream2::new();
+    for (m, adapter) in &adapters {
+        extra.extend(adapters::adapter(args, &name, &item, m, adapter)?);
+    }
+    if args.warn_shadowing {
+        extra.extend(shadowing::check(&item));
+    }
+
+    if args.const_trait {
+        process_impl::strip_constness(&mut item)?;
+    }
+
+    let mut trait_def = impl_to_trait::to_trait(&item, args.vis(), name.clone(), &args.impl_only);
+    impl_to_trait::add_defaults(&mut trait_def, &args.defaults)?;
+    impl_to_trait::move_defaults(&mut trait_def, &mut item, &defaulted, args.self_in_defaults);
+    if args.type_defaults {
+        impl_to_trait::add_type_defaults(&mut trait_def, &item);
+    }
+    if async_trait {
+        trait_def
+            .attrs
+            .push(syn::parse_quote!(#[async_trait::async_trait]));
+    }
+    docs::set_trait_docs(&mut trait_def, &trait_docs);
+    if args.require_docs {
+        docs::require_docs(&trait_def, item.impl_token.span)?;
+    }
+    if args.docs_on_impl {
+        docs::strip_doc_comments(&mut trait_def);
+        // docs that are only meant for the trait stay there
+        docs::set_trait_docs(&mut trait_def, &trait_docs);
+    }
+    if args.doc_links.is_some() {
+        docs::add_target_link(&mut trait_def, &item.self_ty, &item.generics);
+    }
+    if args.split_self_predicates {
+        process_impl::split_self_predicates(&mut trait_def.generics, &mut item);
+    }
+    if args.per_method_bounds {
+        process_impl::per_method_bounds(&mut trait_def, &item);
+    }
+    if args.sorted {
+        impl_to_trait::sort_items(&mut trait_def);
+    }
+    process_impl::resolve_self_projections(&mut item);
+
+    let mut impls = match &args.tuples {
+        Some(arities) => (arities.start..=arities.end_inclusive)
+            .map(|n| tuples::instantiate(&item, &element_bounds, n))
+            .collect::<syn::Result<_>>()?,
+        None => Vec::new(),
+    };
+    let mut self_tys: HashSet<String> = impls
+        .iter()
+        .chain(Some(&item))
+        .map(|i| i.self_ty.to_token_stream().to_string())
+        .collect();
+    for ty in &args.also_for {
+        if self_tys.insert(ty.to_token_stream().to_string()) {
+            let mut other = item.clone();
+            *other.self_ty = ty.clone();
+            for (ident, o) in &overrides {
+                if tokens_eq(&o.target, ty) {
+                    let items = override_items(args, &other, &o.items, &defaulted)?;
+                    other
+                        .items
+                        .retain(|ii| item_args::impl_item_ident(ii) != Some(ident));
+                    other.items.extend(items);
+                }
+            }
+            impls.push(other);
+        }
+    }
+    if let Some((_, o)) = overrides
+        .iter()
+        .find(|(_, o)| !args.also_for.iter().any(|ty| tokens_eq(ty, &o.target)))
+    {
+        return Err(syn::Error::new_spanned(
+            &o.target,
+            "items can only be overridden for the types of `also_for`",
+        ));
+    }
+    for other in &args.other_blocks {
+        impls.push(other_block(args, other.clone(), &name)?);
+    }
+
+    if args.tuples.is_some() && !(args.lift.is_empty() && args.forward.is_empty()) {
+        return Err(syn::Error::new(
+            item.impl_token.span,
+            "`lift` and `forward` can't be combined with `tuples`",
+        ));
+    }
+    for lift in &args.lift {
+        impls.push(forward::lift(&item, &trait_def, *lift, args.span())?);
+    }
+    for fwd in &args.forward {
+        impls.push(forward::forward(&item, &trait_def, *fwd, args.span())?);
+    }
+
+    if args.mock {
+        // mockall needs to know the concrete associated types
+        let assoc_types: Vec<_> = item
+            .items
+            .iter()
+            .filter_map(|ii| match ii {
+                ImplItem::Type(t) => {
+                    let (ident, ty) = (&t.ident, &t.ty);
+                    Some(quote!(type #ident = #ty;))
+                }
+                _ => None,
+            })
+            .collect();
+        let automock = if assoc_types.is_empty() {
+            quote!(mockall::automock)
+        } else {
+            quote!(mockall::automock(#(#assoc_types)*))
+        };
+        trait_def
+            .attrs
+            .push(syn::parse_quote!(#[cfg_attr(test, #automock)]));
+    }
+
+    if args.faux {
+        if !matches!(&*item.self_ty, Type::Path(p) if p.qself.is_none()) {
+            return Err(syn::Error::new_spanned(
+                &item.self_ty,
+                "`faux` can only be used for structs (that are `#[faux::create]`d)",
+            ));
+        }
+        // `faux::methods` has to see the impl before any other attribute macro
+        item.attrs
+            .insert(0, syn::parse_quote!(#[cfg_attr(test, faux::methods)]));
+    }
+
+    if let Some(mut inherent) = inherent {
+        process_impl::substitute_target(&mut inherent)?;
+        forward::to_inherent(&mut item, args.span())?;
+        inherent.attrs.extend(args.cfg_attrs());
+        extra.extend(inherent.into_token_stream());
+    }
+
+    // the impl for the written self type always comes first (and is the only one that isn't cloned)
+    if args.tuples.is_none() {
+        impls.insert(0, item);
+    }
+
+    let cfg = args.cfg_attrs();
+    trait_def.attrs.extend(cfg.iter().cloned());
+    for i in &mut impls {
+        if args.tuples.is_none() {
+            process_impl::substitute_target(i)?;
+        }
+        routing::strip_trait_only(i);
+        if args.deprecated.is_some() {
+            // implementing a deprecated trait is a use of it
+            i.attrs.push(syn::parse_quote!(#[allow(deprecated)]));
+        }
+        if args.slim_impl {
+            routing::slim_impl(i);
+        }
+        if args.doc_links == Some(DocLinks::SeeAlso) {
+            docs::add_see_also(i, &args.trait_path(&name));
+        }
+        if args.inline_all {
+            routing::inline_all(i);
+        }
+        i.attrs.extend(cfg.iter().cloned());
+        if !args.allow.is_empty() {
+            let lints = &args.allow;
+            i.attrs.push(syn::parse_quote!(#[allow(#(#lints),*)]));
+        }
+        if !args.no_automatically_derived {
+            i.attrs.push(syn::parse_quote!(#[automatically_derived]));
+        }
+    }
+    if args.allow_missing_docs {
+        // this also covers the items of the trait
+        trait_def
+            .attrs
+            .push(syn::parse_quote!(#[allow(missing_docs)]));
+    }
+    if !args.allow.is_empty() {
+        let lints = &args.allow;
+        trait_def
+            .attrs
+            .push(syn::parse_quote!(#[allow(#(#lints),*)]));
+    }
+    if !args.warn_dead_code && !matches!(args.vis(), Visibility::Public(_)) {
+        // methods that are only called in some configurations would be reported as unused
+        trait_def.attrs.push(syn::parse_quote!(#[allow(dead_code)]));
+    }
+    match (&args.usage, &args.doctest_example) {
+        (Some(module), example) => docs::add_usage(&mut trait_def, module, example.as_ref())?,
+        (None, Some(example)) => {
+            return Err(syn::Error::new_spanned(
+                example,
+                "`doctest_example` needs `usage = path::to::module`",
+            ))
+        }
+        (None, None) => {}
+    }
+    if let Some(flag) = &args.doc_cfg {
+        docs::add_doc_cfg(&mut trait_def, flag);
+    }
+    trait_def.attrs.extend(args.deprecated.clone());
+    if args.const_trait {
+        trait_def.attrs.push(syn::parse_quote!(#[const_trait]));
+    }
+    if let Some(flag) = &args.notable {
+        trait_def
+            .attrs
+            .push(syn::parse_quote!(#[cfg_attr(#flag, doc(notable_trait))]));
+    }
+
+    if args.dyn_clone {
+        trait_def
+            .supertraits
+            .push(syn::parse_quote!(dyn_clone::DynClone));
+        for i in &mut impls {
+            // `DynClone` is implemented for all `Clone` types
+            i.generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(Self: Clone));
+        }
+        extra.extend(clone_trait_object(args, &trait_def));
+    }
+
+    if args.downcast {
+        trait_def
+            .supertraits
+            .push(syn::parse_quote!(downcast_rs::Downcast));
+        for i in &mut impls {
+            // `Downcast` is implemented for all `Any` types
+            i.generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(Self: std::any::Any));
+        }
+        extra.extend(impl_downcast(args, &trait_def)?);
+    }
+
+    if args.sealed {
+        let sealed_mod = format_ident!("__{}_sealed", name);
+        trait_def
+            .supertraits
+            .push(syn::parse_quote!(#sealed_mod::Sealed));
+        docs::add_sealed_note(&mut trait_def);
+        extra.extend(quote! {
+            #(#cfg)*
+            #[doc(hidden)]
+            #[allow(non_snake_case)]
+            mod #sealed_mod {
+                pub trait Sealed {}
+            }
+        });
+        let glue = args.glue_attrs();
+        for item in &impls {
+            let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+            let self_ty = &item.self_ty;
+            extra.extend(quote! {
+                #(#glue)*
+                impl #impl_generics #sealed_mod::Sealed for #self_ty #where_clause {}
+            });
+        }
+    }
+
+    let impls = impls.iter().map(|i| {
+        if args.const_trait {
+            process_impl::const_impl(i)
+        } else {
+            i.to_token_stream()
+        }
+    });
+    Ok((trait_def, quote!(#(#impls)* #extra)))
+}
+
+/// Turn an inherent impl into an extension trait and an impl of it.
+///
+/// The arguments are an optional visibility and trait name, followed by a comma-separated list of
+/// options. With a path like `traits::BytesExt` as the name, the trait is put into a module
+/// `traits` (which is generated as well, so there can only be one such block per module).
+/// Without a visibility, the trait is private.
+///
+/// The options are:
+/// - `vis = infer`: without a visibility, give the trait the one that all items of the impl are
+///   written with, if they agree (e.g. it's `pub` if all items are `pub fn`s and private
+///   otherwise)
+/// - `name = "{type}Ext"`: name the trait after the self type (or its first bound, for blanket
+///   impls like `impl<I: Iterator> I`)
+/// - `type_name(wrappers = first | last, generics = include | omit, paths = last | full)`: how
+///   `{type}` is spelled, e.g. `&'a Vec<u8>` is `RefVecU8` by default, `VecU8Ref` with
+///   `wrappers = last` (which also applies to pointers, slices and arrays) and `RefVec` with
+///   `generics = omit`. `paths = full` includes all path segments (`std::vec::Vec` => `StdVecVec`).
+///   Lifetimes and const arguments (like `N` in `[u8; N]` or `Grid<N>`) are always left out.
+/// - `id = "..."`: derive the generated name from this instead of the impl (see `ext_trait_name!`)
+/// - `sealed`: prevent the trait from being implemented anywhere else (which its docs point out)
+/// - `hidden_module`: define the trait in a `#[doc(hidden)]` module and only `use` it here
+/// - `pub_if(predicate)`: instead of a visibility, make the trait (or its module) `pub` when the
+///   `cfg` predicate holds and `pub(crate)` otherwise, e.g. `pub_if(feature = "test-util")`
+/// - `alias = pub Name`: also re-export the trait under another name
+/// - `prelude`: register the trait for `ext_prelude!`
+/// - `also_for(A, B, ...)`, `for_primitives(signed, unsigned, floats)`, `for_floats`:
+///   additionally implement the trait for other self types. Items that have to differ for one of
+///   them can be replaced with `#[ext(for(A) { ... })]`, e.g.
+///   `#[ext(for(u16) { const BITS: u32 = 16; })] const BITS: u32 = 8;`. In bodies, constant
+///   values and associated types, `__Target` stands for the self type of each impl, which also
+///   works where `Self` can't be used, like in array lengths.
+/// - `defaults(T = u8, ...)`: give the type parameters of the trait defaults, so that e.g. `Ext`
+///   can be written instead of `Ext<u8>` in bounds
+/// - `also_inherent`: keep the inherent impl as well (so that its methods can be called without
+///   importing the trait) and make the trait impl call it. This only works for local types, and
+///   not with associated types (which inherent impls can't have).
+/// - `lift(Option, Result)`: also implement the trait for `Option<Type>` and/or `Result<Type, E>`
+///   (for any `E`), with methods that call the ones of `Type` for `Some` and `Ok` values and
+///   return `Default::default()` otherwise. Methods that mention `Self` (other than `self`) or
+///   return generic types aren't supported.
+/// - `forward(Cow)`: also implement the trait for `Cow<'_, Type>`, with methods that call the
+///   ones of `Type` with the borrowed value. Only methods that take `&self` (or no `self` at all)
+///   are supported, and they can't mention `Self` otherwise.
+/// - `also_fns` or `also_fns = module`: also generate a free function for each method, which
+///   takes the receiver as its first argument `recv` (e.g. `fn double(recv: &u8) -> u8`) and calls
+///   the method, next to the impl or in a new module `module`. `Self` is replaced with the self
+///   type, but `Self::Assoc` types aren't supported.
+/// - `newtype = Name`: also generate `struct Name(pub Type);` for the self type `Type`, with
+///   `Deref` and `From<Type>` impls and an impl of the trait that calls the one of `Type`. Generic
+///   impls and methods that mention `Self` (other than `self`) aren't supported. With
+///   `dyn_clone`, the struct derives `Clone`.
+/// - `delegatable`: make the trait usable with [`#[ext_delegate]`](macro@ext_delegate) within the
+///   crate. Generic traits and methods that mention `Self` (other than `self`) aren't supported.
+/// - `tuples(a..=b)`: implement the trait for tuples, with `Tuple` as the self type placeholder
+/// - `split_self_predicates`: instead of having both forms of predicates on the self type (e.g.
+///   `Vec<u8>: Clone` and `Self: Clone`) everywhere, only use `Self` in the trait and only the
+///   self type in the impl
+/// - `per_method_bounds`: move the bounds from the trait onto the methods that mention the bounded
+///   generic parameters (or `Self`), so that other implementers don't need to satisfy them.
+///   Methods can also have their own `where` clauses, which always stay on the method.
+/// - `split_mut`: put the methods that take `&mut self` into a separate trait (`FooExtMut` for
+///   `FooExt`) with its own impl, so that code that only reads can depend on the smaller trait
+/// - `split_consuming`: put the methods that take `self` by value into a separate trait
+///   (`IntoFooExt` for `FooExt`) that requires `Self: Sized`, like `Iterator` and its adapters, so
+///   that the main trait stays object safe
+/// - `sorted`: order the items of the trait (but not of the impl) by kind (constants, types, then
+///   methods) and name, so that the docs of large traits are organized
+/// - `no_auto_sized`: don't add `where Self: Sized` to methods that need it
+/// - `deprecated` or `deprecated = "note"`: deprecate the trait (its own impls don't cause
+///   warnings). `#[deprecated]` on items of the impl only ends up on the trait, where it takes
+///   effect.
+/// - `cfg(...)`: put the `#[cfg(...)]` on everything that is generated
+/// - `allow_missing_docs`: allow the `missing_docs` lint on the trait (and the module it's put
+///   in), for crates that document their extension methods sparsely
+/// - `allow(lint, ...)`: allow the lints (like `clippy::needless_lifetimes`) on the trait and its
+///   impls, but not on anything else that is generated
+/// - `msrv = "1.N"`: generate code that compiles with Rust 1.N. Before 1.75, `async fn`s are
+///   implemented with [`async_trait`](https://docs.rs/async-trait) (which the crate then has to
+///   depend on) instead of being `async fn`s in the trait, and `impl Trait` return types are an
+///   error; before 1.65, generic associated types are an error; before 1.87, `use<...>` bounds
+///   are an error. `#[ext_mod(msrv = "...")]` sets it for a whole module.
+/// - `require_docs`: make it an error if the impl (which the docs of the trait come from) or any
+///   of its items doesn't have a doc comment, for traits that are part of a public API
+/// - `warn_dead_code`: report unused methods of a trait that isn't `pub`. The `dead_code` lint is
+///   allowed on such traits by default (and only on the traits, not on other code of the crate).
+/// - `usage = path::to::module`: add a "Usage" section to the docs of the trait that shows how to
+///   import it from that module and call its first method
+/// - `doctest_example = expr`: make the code in the "Usage" section a doctest that calls the first
+///   method without arguments on `expr` (the `usage` path has to start with the name of the crate
+///   then, since it's used from outside)
+/// - `doc_links` or `doc_links(see_also)`: add an intra-doc link to the extended type to the docs
+///   of the trait (for blanket impls, to the first bound). With `see_also`, the documented
+///   methods of the impl also link back to the trait.
+/// - `doc_cfg` or `doc_cfg = flag`: when `docsrs` (or `flag`) is set, document the `#[cfg]`s
+///   of the trait and its items with `#[doc(cfg)]`
+/// - `notable` or `notable = flag`: when `docsrs` (or `flag`) is set, mark the trait
+///   `#[doc(notable_trait)]`, so that rustdoc points it out on functions returning the type
+///   (this needs `#![feature(doc_notable_trait)]` for that case)
+/// - `coverage_off(flag)` or `coverage_off(flag, no_coverage)`: when `flag` (e.g.
+///   `coverage_nightly`) is set, exclude generated glue code (but not the bodies written in the
+///   impl) from coverage reports with `#[coverage(off)]` (or `#[no_coverage]`). `flag` has to be
+///   declared with `check-cfg`, e.g. `println!("cargo:rustc-check-cfg=cfg(coverage_nightly)")` in
+///   the build script, to avoid `unexpected_cfgs` warnings
+/// - `no_automatically_derived`: don't mark the generated impls as `#[automatically_derived]`
+/// - `impl_only(path, ...)`: keep these attributes off the trait (`lints` stands for `allow`,
+///   `warn`, `deny`, `forbid` and `expect`)
+/// - `copy_attrs(path, ...)`: copy these attributes to the trait, even though they aren't built
+///   in, e.g. `copy_attrs(async_trait::async_trait)`
+/// - `slim_impl`: leave doc comments off the generated impl to make the expansion smaller; they
+///   are still on the trait
+/// - `docs_on_impl`: the opposite of `slim_impl`: leave doc comments off the trait, for crates
+///   whose docs are read in the source rather than with rustdoc
+/// - `inline_all`: mark every method of the generated impls `#[inline]`
+/// - `debug_dump`: write the expansion to `$EXT_TRAIT_DEBUG_DIR/crate/file/Name-line.rs` (or
+///   into `ext_trait` in the temporary directory), where `file` is the file of the impl (e.g.
+///   `src_lib_rs`) and `line` its line. The dump isn't formatted, so run `rustfmt` on it to read
+///   it. Setting `EXT_TRAIT_DEBUG_DIR` dumps every expansion without needing the option. To see
+///   the formatted expansions of all blocks in a single file, run
+///   `cargo run --features cli --bin ext_trait_expand -- path/to/file.rs` in this crate.
+/// - `warn_shadowing`: warn about methods that have the name of a method of a prominent std trait
+///   (like `clone` or `into_iter`), since those make method resolution confusing
+/// - `const` (needs the `const_trait` feature and a nightly compiler that supports
+///   `#[const_trait]`, with `#![feature(const_trait_impl)]`): make the trait a `#[const_trait]` and the impls
+///   `impl const`, so that the methods can be called in const contexts. All methods have to be
+///   `const fn`s (which only the inherent impl could say, since a const trait's methods are const
+///   implicitly).
+/// - `type_defaults` (needs the `associated_type_defaults` feature and a nightly compiler, with
+///   `#![feature(associated_type_defaults)]`): make the associated types of the impl (like
+///   `type Unit = usize;`) the defaults of the ones of the trait as well, so that other
+///   implementers only have to give the types that differ. The impl still sets them explicitly.
+/// - `mixed_site`: give the local variables that generated code introduces (like the argument
+///   names of forwarding methods) `Span::mixed_site()` hygiene instead of `Span::call_site()`, so
+///   that they can't interact with the identifiers of a `macro_rules!` macro that the block is
+///   written in. Item names (like those of the trait and of generated structs) always resolve at
+///   the call site, since item hygiene (`Span::def_site()`) isn't stable.
+/// - `dyn_clone`: make the trait require [`dyn_clone::DynClone`](https://docs.rs/dyn-clone) and
+///   implement `Clone` for `Box<dyn Trait>` with `dyn_clone::clone_trait_object!`, for traits that
+///   are used as cloneable trait objects. The impls get a `where Self: Clone` bound.
+/// - `downcast`: make the trait require [`downcast_rs::Downcast`](https://docs.rs/downcast-rs)
+///   and add the downcasting methods (like `is::<T>()` and `downcast_ref::<T>()`) to its trait
+///   objects with `downcast_rs::impl_downcast!`. The impls get a `where Self: Any` bound. Lifetime
+///   and const parameters and more than one associated type aren't supported.
+/// - `mock`: derive a [`mockall`](https://docs.rs/mockall) mock of the trait in test builds
+/// - `faux`: make the impl `#[cfg_attr(test, faux::methods)]`, for structs that are mocked with
+///   [`faux`](https://docs.rs/faux) (and marked `#[cfg_attr(test, faux::create)]`)
+/// - `gen_spy = SpyName`: in test builds, also generate a `SpyName` struct that wraps a value of
+///   the self type (`SpyName::new(value)`) and implements the trait by calling its methods, while
+///   recording the calls (`spy.calls()`, e.g. `["push(1)", "apply(_)"]`, with `_` for arguments
+///   that don't implement `Debug`). Generic impls and methods that mention `Self` (other than
+///   `self`) aren't supported. With `dyn_clone`, the struct derives `Clone`.
+/// - `assert_impl_for(Type, ...)`: check at compile time that these types implement the trait,
+///   e.g. through `also_for` or a blanket impl, so that changes to the bounds don't silently
+///   drop them
+/// - `dummy_impl(Type, ...)` or `dummy_impl(Type, ..., bodies = default)`: also implement the
+///   trait for these types, with methods that panic with `unimplemented!()` (or return
+///   `Default::default()`), e.g. for stand-ins of backends that are disabled by a feature. Traits
+///   with associated types or constants aren't supported. With `dyn_clone` or `downcast`, the
+///   types have to be `Clone` or `'static`, since they get those supertraits from blanket impls.
+/// - `gen_mock = MockName`: in test builds, also generate a `MockName` struct that implements the
+///   trait without any dependencies. Its methods return what the closure set with
+///   `set_method(|| ...)` returns (or panic with the name of the method if there is none), except
+///   for methods returning `()`, which do nothing by default. Methods without `self` or with
+///   borrowed or generic return values always panic. Generic traits, traits with associated
+///   types or constants and `dyn_clone` (since the closures can't be cloned) aren't supported.
+/// - `self_in_defaults`: spell the self type as `Self` in the bodies of `#[ext(default)]` items
+///   (see below), so that `Vec::<u8>::new()` becomes `Self::new()` and the default also works for
+///   other implementers. Only the self type exactly as it's written is replaced (not e.g.
+///   `Vec::new()`), and nothing inside macro invocations.
+///
+/// Items can be put into separate traits with `#[ext(group = "...")]`, renamed (in both the
+/// trait and the impl) with `#[ext(rename = "...")]` and hidden from the docs (while staying
+/// callable) with `#[ext(hide)]`. `#[ext(trait_doc = "...")]` replaces the doc comment of an
+/// item in the trait, e.g. to state a contract there while the impl keeps its implementation
+/// notes. Methods and constants marked with `#[ext(default)]` become defaults in the trait
+/// instead of being implemented. A method that takes `self` and returns an iterator adapter can be
+/// marked with `#[ext(adapter = Name)]` to generate `struct Name<I, ...>`, with the receiver in
+/// its `iter` field and the other arguments in fields of the same names. Its generic parameters
+/// are the method's lifetimes and (after `I`) other parameters that the arguments use, without
+/// bounds. The method still constructs it and the `Iterator` impl is left to you. To avoid clashing
+/// with other macros' helper attributes, these can also be written as `#[ext_trait::ext(...)]` or
+/// `#[ext_trait::group = "..."]`, `#[ext_trait::rename = "..."]`, `#[ext_trait::hide]`,
+/// `#[ext_trait::default]`, `#[ext_trait::trait_doc = "..."]` and `#[ext_trait::adapter = Name]`.
 #[proc_macro_attribute]
 pub fn ext(args: TokenStream, input: TokenStream) -> TokenStream {
-    let input_hash = hash(&input);
+    let args = parse_macro_input!(args as ExtArgs);
+    expand_attr(args, input.into()).into()
+}
+
+/// Like [`#[ext]`](macro@ext), but also exports a macro that other crates can use to implement
+/// the trait (which has to be `pub`, named and not generic) for their own types.
+///
+/// For a trait `FooExt`, it's called `impl_foo_ext!`. It takes the type and the items of the
+/// impl, like `my_crate::impl_foo_ext!(TheirType { ... })` or
+/// `my_crate::impl_foo_ext!(impl<T: Clone> TheirType<T> { ... })`, and reports items that the
+/// trait doesn't have, required ones that are missing, and methods whose kind of receiver
+/// (`self`, `&self`, ...) or number of arguments differs from the trait's. The types of the
+/// arguments are left to the compiler. Like all exported macros, it's at the
+/// root of the crate, so it needs to know where the trait is: unless the trait is in the root
+/// module, give its module with `decl_module = path::to::module`.
+/// ```
+/// mod exts {
+///     use ext_trait::ext_decl;
+///
+///     #[ext_decl(pub Halve, decl_module = exts)]
+///     impl u32 {
+///         pub fn halve(self) -> Self { self / 2 }
+///     }
+///
+///     // in another crate, this would be `the_crate::impl_halve!`
+///     impl_halve!(u8 {
+///         fn halve(self) -> Self { self / 2 }
+///     });
+/// }
+///
+/// fn main() {
+///     use exts::Halve;
+///     assert_eq!((9u32.halve(), 9u8.halve()), (4, 4));
+/// }
+/// ```
+///
+/// A method that takes `&self` where the trait's takes `self` is reported at the method:
+/// ```compile_fail
+/// mod exts {
+///     use ext_trait::ext_decl;
+///
+///     #[ext_decl(pub Halve, decl_module = exts)]
+///     impl u32 {
+///         pub fn halve(self) -> Self { self / 2 }
+///     }
+///
+///     impl_halve!(u8 {
+///         fn halve(&self) -> Self { *self / 2 }
+///     });
+/// }
+/// # fn main() {}
+/// ```
+#[proc_macro_attribute]
+pub fn ext_decl(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut args = parse_macro_input!(args as ExtArgs);
+    args.decl = true;
+    expand_attr(args, input.into()).into()
+}
+
+#[doc(hidden)]
+#[proc_macro]
+pub fn __ext_impl(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as decl::DeclImpl);
+    decl::implement(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
 
-    let mut item = parse_macro_input!(input as ItemImpl);
+/// The expansion of `#[ext]` and `#[ext_decl]`
+fn expand_attr(mut args: ExtArgs, input: TokenStream2) -> TokenStream2 {
+    // cloning is cheap (it only clones a handle), hashing isn't
+    let item = match syn::parse2::<ItemImpl>(input.clone()) {
+        Ok(item) => item,
+        Err(e) => return e.to_compile_error(),
+    };
     if item.trait_.is_some() {
         panic!("Only inherent impls can become an ext trait");
     }
-    process_impl::move_bounds_to_where_clause(&mut item);
 
+    let tracking = match config::apply(&mut args) {
+        Ok(tracking) => tracking,
+        Err(e) => return e.to_compile_error(),
+    };
+    args.infer_vis(&item);
+    if let Some(target) = &args.append_to {
+        return syn::Error::new(
+            target.span(),
+            "`append_to` can only be used inside `#[ext_mod]`",
+        )
+        .to_compile_error();
+    }
+    if let (Some(path), false) = (&args.decl_module, args.decl) {
+        return syn::Error::new_spanned(path, "`decl_module` can only be used with `#[ext_decl]`")
+            .to_compile_error();
+    }
+    if args.decl && args.ident.is_none() && args.name_template.is_none() {
+        return syn::Error::new(item.impl_token.span, "`ext_decl` needs a trait name")
+            .to_compile_error();
+    }
+
+    naming::trait_ident(&args, &item, || hash_input(&input, &item))
+        .and_then(|name| {
+            let (expanded, names) = expand_groups(&args, item, name)?;
+            let prelude = if args.prelude {
+                prelude_module(prelude_registrations(&args, &names))
+            } else {
+                TokenStream2::new()
+            };
+            Ok(quote!(#expanded #prelude #tracking))
+        })
+        .unwrap_or_else(|e| e.to_compile_error())
+}
+
+/// The re-exports of the given traits, for the module that `ext_prelude!` looks for
+fn prelude_registrations(args: &ExtArgs, names: &[Ident]) -> TokenStream2 {
+    let attrs = args.reexport_attrs();
+    names
+        .iter()
+        .map(|name| {
+            let path = args.trait_path(name);
+            quote!(#(#attrs)* pub use super::#path as _;)
+        })
+        .collect()
+}
+
+fn prelude_module(registrations: TokenStream2) -> TokenStream2 {
+    quote! {
+        #[doc(hidden)]
+        pub mod __ext_prelude {
+            #registrations
+        }
+    }
+}
+
+/// The input of `ext_trait_name!`
+struct NameAlias {
+    vis: Visibility,
+    alias: Ident,
+    name: Ident,
+}
+
+impl Parse for NameAlias {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        let alias = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let name = if input.peek(Token![impl]) {
+            return Err(input.error(
+                "traits without an `id` can't be named, since their names depend on where the \
+                 impl is; give it an `id` and name it with that",
+            ));
+        } else {
+            naming::hashed_ident("", hash(&input.parse::<LitStr>()?.value()))
+        };
+        Ok(NameAlias { vis, alias, name })
+    }
+}
+
+/// Give a name to an auto-named ext trait.
+///
+/// Since macros can't be used in trait bound position, this expands to a `use` declaration.
+/// `ext_trait_name!(pub U8Ext = "some id")` names the trait generated by `#[ext(id = "some id")]`.
+/// Other auto-generated names can't be reproduced, since they also depend on where the impl is.
+#[proc_macro]
+pub fn ext_trait_name(input: TokenStream) -> TokenStream {
+    let NameAlias { vis, alias, name } = parse_macro_input!(input as NameAlias);
+
+    quote!(#vis use #name as #alias;).into()
+}
+
+/// Whether `path` is `ext` or `ext_trait::ext` (other attributes named `ext` are left alone)
+fn is_ext_path(path: &Path) -> bool {
+    let segments: Vec<_> = path.segments.iter().map(|s| &s.ident).collect();
+    match segments[..] {
+        [ext] => path.leading_colon.is_none() && ext == "ext",
+        [krate, ext] => krate == "ext_trait" && ext == "ext",
+        _ => false,
+    }
+}
+
+/// The tokens of the attribute after its path, like `(...)` or `= value`
+fn attr_tokens(attr: &Attribute) -> TokenStream2 {
+    match &attr.meta {
+        Meta::Path(_) => TokenStream2::new(),
+        Meta::List(list) => {
+            let tokens = &list.tokens;
+            match list.delimiter {
+                MacroDelimiter::Paren(_) => quote!((#tokens)),
+                MacroDelimiter::Brace(_) => quote!({ #tokens }),
+                MacroDelimiter::Bracket(_) => quote!([#tokens]),
+            }
+        }
+        Meta::NameValue(nv) => {
+            let (eq, value) = (&nv.eq_token, &nv.value);
+            quote!(#eq #value)
+        }
+    }
+}
+
+/// Parse the `(...)` after an `ext` attribute path
+fn parse_ext_args(tokens: TokenStream2) -> syn::Result<ExtArgs> {
+    if tokens.is_empty() {
+        return Ok(ExtArgs::default());
+    }
+    (|input: ParseStream| {
+        let content;
+        syn::parenthesized!(content in input);
+        content.parse()
+    })
+    .parse2(tokens)
+}
+
+/// The contents of a `#[cfg_attr(predicate, attr, ...)]`
+struct CfgAttr {
+    predicate: syn::Meta,
+    attrs: Vec<(Path, TokenStream2)>,
+}
+
+impl Parse for CfgAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let predicate = input.parse()?;
+        let mut attrs = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let path = input.call(Path::parse_mod_style)?;
+            let mut tokens = TokenStream2::new();
+            while !input.is_empty() && !input.peek(Token![,]) {
+                tokens.extend(Some(input.parse::<proc_macro2::TokenTree>()?));
+            }
+            attrs.push((path, tokens));
+        }
+        Ok(CfgAttr { predicate, attrs })
+    }
+}
+
+/// Take the `#[ext(...)]` attribute off of an impl inside an `#[ext_mod]`, if there is one.
+///
+/// For `#[cfg_attr(predicate, ext(...))]`, the predicate is returned as well.
+fn take_ext_attr(item: &mut ItemImpl) -> syn::Result<(ExtArgs, Option<syn::Meta>)> {
+    for pos in 0..item.attrs.len() {
+        let attr = &item.attrs[pos];
+        if is_ext_path(attr.path()) {
+            let attr = item.attrs.remove(pos);
+            return Ok((parse_ext_args(attr_tokens(&attr))?, None));
+        }
+        if attr.path().is_ident("cfg_attr") {
+            let mut cfg_attr: CfgAttr = attr.parse_args()?;
+            if let Some(i) = cfg_attr.attrs.iter().position(|(p, _)| is_ext_path(p)) {
+                let (_, tokens) = cfg_attr.attrs.remove(i);
+                let args = parse_ext_args(tokens)?;
+                let predicate = cfg_attr.predicate;
+                if cfg_attr.attrs.is_empty() {
+                    item.attrs.remove(pos);
+                } else {
+                    let rest = cfg_attr.attrs.iter().map(|(p, ts)| quote!(#p #ts));
+                    item.attrs[pos] = syn::parse_quote!(#[cfg_attr(#predicate, #(#rest),*)]);
+                }
+                return Ok((args, Some(predicate)));
+            }
+        }
+    }
+    Ok((ExtArgs::default(), None))
+}
+
+fn expand_mod(mut args: ExtArgs, mut module: ItemMod) -> syn::Result<TokenStream2> {
+    args.check_inheritable("`#[ext_mod]`")?;
+    let tracking = config::apply(&mut args)?;
+    let items = match &mut module.content {
+        Some((_, items)) => items,
+        None => {
+            return Err(syn::Error::new_spanned(
+                &module,
+                "`ext_mod` can only be used on inline modules",
+            ))
+        }
+    };
+
+    // all impls are collected first, so that `append_to` blocks can be merged into their targets
+    let mut impls: Vec<(usize, ItemImpl, ExtArgs, TokenStream2)> = Vec::new();
+    // the position in `impls` of the first block with each trait name
+    let mut named: HashMap<Ident, usize> = HashMap::new();
+    for (idx, item) in items.iter_mut().enumerate() {
+        let mut i = match item {
+            Item::Impl(i) if i.trait_.is_none() => {
+                match std::mem::replace(item, Item::Verbatim(TokenStream2::new())) {
+                    Item::Impl(i) => i,
+                    _ => unreachable!(),
+                }
+            }
+            _ => continue,
+        };
+        let (mut item_args, predicate) = take_ext_attr(&mut i)?;
+        item_args.inherit(&args);
+        item_args.infer_vis(&i);
+        let mut fallback = TokenStream2::new();
+        if let Some(predicate) = predicate {
+            // without the `cfg_attr`, the impl stays as it is
+            let original = &i;
+            fallback = quote!(#[cfg(not(#predicate))] #original);
+            item_args.cfg.push(predicate.into_token_stream());
+        }
+
+        match &item_args.append_to {
+            Some(target) => {
+                let (_, base, _, _) =
+                    named
+                        .get(target)
+                        .map(|&pos| &mut impls[pos])
+                        .ok_or_else(|| {
+                            syn::Error::new(
+                                target.span(),
+                                format!("there's no `#[ext({})]` block before this one", target),
+                            )
+                        })?;
+                if !tokens_eq(&base.self_ty, &i.self_ty)
+                    || !tokens_eq(&base.generics, &i.generics)
+                    || !tokens_eq(&base.generics.where_clause, &i.generics.where_clause)
+                {
+                    return Err(syn::Error::new_spanned(
+                        &i.self_ty,
+                        "appended blocks need the same self type and generics as their target",
+                    ));
+                }
+                base.items.extend(i.items);
+                *item = Item::Verbatim(TokenStream2::new());
+            }
+            None => {
+                if let Some(ident) = &item_args.ident {
+                    named.entry(ident.clone()).or_insert(impls.len());
+                }
+                impls.push((idx, i, item_args, fallback))
+            }
+        }
+    }
+
+    let mut prelude = TokenStream2::new();
+    for (idx, i, item_args, fallback) in impls {
+        let name = naming::trait_ident(&item_args, &i, || hash_input(&i.to_token_stream(), &i))?;
+        let (expanded, names) = expand_groups(&item_args, i, name)?;
+        if item_args.prelude {
+            prelude.extend(prelude_registrations(&item_args, &names));
+        }
+        items[idx] = Item::Verbatim(quote!(#expanded #fallback));
+    }
+    if !prelude.is_empty() {
+        items.push(Item::Verbatim(prelude_module(prelude)));
+    }
+    items.push(Item::Verbatim(tracking));
+
+    Ok(module.into_token_stream())
+}
+
+/// The input of `ext_prelude!`
+struct Prelude {
+    vis: Visibility,
+    ident: Ident,
+    modules: Punctuated<Path, Token![,]>,
+}
+
+impl Parse for Prelude {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        input.parse::<Token![mod]>()?;
+        let ident = input.parse()?;
+        let content;
+        syn::braced!(content in input);
+        Ok(Prelude {
+            vis,
+            ident,
+            modules: content.parse_terminated(Path::parse_mod_style, Token![,])?,
+        })
+    }
+}
+
+/// Generate a prelude module re-exporting the ext traits registered with `#[ext(prelude)]`.
+///
+/// `ext_prelude!(pub mod prelude { crate::a, crate::b::c })` collects the traits registered in the
+/// listed modules. Traits are re-exported anonymously (`as _`), so a glob import of the prelude
+/// makes their methods available without adding any names to the scope.
+#[proc_macro]
+pub fn ext_prelude(input: TokenStream) -> TokenStream {
+    let Prelude {
+        vis,
+        ident,
+        modules,
+    } = parse_macro_input!(input as Prelude);
+    let modules = modules.iter();
+
+    quote! {
+        #vis mod #ident {
+            #(pub use #modules::__ext_prelude::*;)*
+        }
+    }
+    .into()
+}
+
+/// A trait with the bounds as supertraits and a blanket impl for everything that satisfies them
+struct BlanketTrait {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    ident: Ident,
+    generics: Generics,
+    bounds: Punctuated<TypeParamBound, Token![+]>,
+}
+
+impl BlanketTrait {
+    /// Parse `Name<...> <sep> Bounds where ...;`, with `sep` being `:` (`ext_combine!`) or `=`
+    /// (`ext_alias!`) and the `;` being optional
+    fn parse_rest(
+        input: ParseStream,
+        attrs: Vec<Attribute>,
+        vis: Visibility,
+        parse_sep: fn(ParseStream) -> syn::Result<()>,
+    ) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        let mut generics: Generics = input.parse()?;
+        parse_sep(input)?;
+        let bounds = Punctuated::parse_separated_nonempty(input)?;
+        generics.where_clause = input.parse()?;
+        input.parse::<Option<Token![;]>>()?;
+        Ok(BlanketTrait {
+            attrs,
+            vis,
+            ident,
+            generics,
+            bounds,
+        })
+    }
+
+    fn expand(&self) -> TokenStream2 {
+        let BlanketTrait {
+            attrs,
+            vis,
+            ident,
+            generics,
+            bounds,
+        } = self;
+        let (_, ty_generics, where_clause) = generics.split_for_impl();
+        let mut impl_generics = generics.clone();
+        impl_generics
+            .params
+            .push(syn::parse_quote!(__ExtImplementor: ?Sized + #bounds));
+        let (impl_generics, _, _) = impl_generics.split_for_impl();
+        quote! {
+            #(#attrs)*
+            #vis trait #ident #generics: #bounds #where_clause {}
+
+            impl #impl_generics #ident #ty_generics for __ExtImplementor #where_clause {}
+        }
+    }
+}
+
+/// The input of `ext_combine!`
+struct Combine(BlanketTrait);
+
+impl Parse for Combine {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![trait]>()?;
+        BlanketTrait::parse_rest(input, attrs, vis, |input| {
+            input.parse::<Token![:]>().map(drop)
+        })
+        .map(Combine)
+    }
+}
+
+/// Combine several (ext) traits into one, so that a single bound makes all of their methods
+/// available.
+///
+/// `ext_combine!(pub trait AllExt: ReadExt + WriteExt);` expands to the trait along with an impl
+/// for every type that implements all of the listed traits.
+///
+/// Note that Rust only looks at supertraits for generic parameters bounded by `AllExt`: calling
+/// the methods on a concrete type still requires importing the individual traits.
+#[proc_macro]
+pub fn ext_combine(input: TokenStream) -> TokenStream {
+    let Combine(blanket) = parse_macro_input!(input as Combine);
+    blanket.expand().into()
+}
+
+/// The input of `ext_alias!`
+struct Alias(BlanketTrait);
+
+impl Parse for Alias {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        BlanketTrait::parse_rest(input, attrs, vis, |input| {
+            input.parse::<Token![=]>().map(drop)
+        })
+        .map(Alias)
+    }
+}
+
+/// Emulate a trait alias (which is still unstable).
+///
+/// `ext_alias!(pub BytesIter = Iterator<Item = u8> + Send);` expands to a trait `BytesIter` with
+/// those bounds as supertraits and an impl for every type that satisfies them, so it can be used
+/// in bounds like `fn f(it: impl BytesIter)`.
+#[proc_macro]
+pub fn ext_alias(input: TokenStream) -> TokenStream {
+    let Alias(blanket) = parse_macro_input!(input as Alias);
+    blanket.expand().into()
+}
+
+/// Implement an ext trait for a wrapper struct by calling the impl of one of its fields.
+///
+/// `#[ext_delegate(MyExt, field = inner)]` on a struct implements `MyExt` for it, with methods that
+/// call the ones of the `inner` field, whose type has to implement `MyExt`. `field = ...` can be
+/// left out for structs with exactly one field. The trait has to be generated with
+/// `#[ext(MyExt, delegatable)]` (in the same crate) and supertraits need impls of their own.
+#[proc_macro_attribute]
+pub fn ext_delegate(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as forward::DelegateArgs);
+    let item = parse_macro_input!(input as syn::ItemStruct);
+
+    forward::delegate(&args, &item)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// The input of `ext_block!`
+struct ExtBlock {
+    args: ExtArgs,
+    first: ItemImpl,
+}
+
+impl Parse for ExtBlock {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attrs = input.call(Attribute::parse_outer)?;
+        let mut args = match attrs.iter().position(|a| is_ext_path(a.path())) {
+            Some(pos) => parse_ext_args(attr_tokens(&attrs.remove(pos)))?,
+            None => ExtArgs::default(),
+        };
+        let vis: Visibility = input.parse()?;
+        if !matches!(vis, Visibility::Inherited) {
+            args.vis = Some(vis);
+        }
+        input.parse::<Token![trait]>()?;
+        args.ident = Some(input.parse()?);
+        input.parse::<Token![;]>()?;
+
+        let mut first: ItemImpl = input.parse()?;
+        // the attributes of the trait end up on it the same way as with `#[ext]`
+        attrs.append(&mut first.attrs);
+        first.attrs = attrs;
+        while !input.is_empty() {
+            let other: ItemImpl = input.parse()?;
+            let params = |i: &ItemImpl| {
+                let g = &i.generics;
+                (
+                    g.lifetimes().count(),
+                    g.type_params().count(),
+                    g.const_params().count(),
+                )
+            };
+            if params(&other) != params(&first) {
+                return Err(syn::Error::new_spanned(
+                    &other.generics,
+                    "all blocks of `ext_block!` need the same generic parameters",
+                ));
+            }
+            args.other_blocks.push(other);
+        }
+        for block in Some(&first).into_iter().chain(&args.other_blocks) {
+            if let Some((_, path, _)) = &block.trait_ {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    "`ext_block!` only takes inherent impls",
+                ));
+            }
+        }
+        if args.tuples.is_some() || args.append_to.is_some() {
+            return Err(syn::Error::new(
+                first.impl_token.span,
+                "`ext_block!` can't be combined with `tuples` or `append_to`",
+            ));
+        }
+        args.infer_vis(&first);
+        Ok(ExtBlock { args, first })
+    }
+}
+
+/// Generate one trait from several inherent impls, each of which becomes an impl of it.
+///
+/// `ext_block! { pub trait Stringish; impl &str { ... } impl String { ... } }` defines the trait
+/// `Stringish` with the items of the first block and implements it for both types with their own
+/// bodies, unlike `also_for`, which reuses the bodies of the block. The blocks need the same
+/// generic parameters, since those become the ones of the trait. Attributes in front of `trait`
+/// (like its docs) are treated like those of the first block, and `#[ext(...)]` among them sets
+/// the other options; items can't be put into separate traits, though.
+#[proc_macro]
+pub fn ext_block(input: TokenStream) -> TokenStream {
+    let ExtBlock { mut args, first } = parse_macro_input!(input as ExtBlock);
+    let name = args.ident.clone().expect("`ext_block!` names the trait");
+
+    config::apply(&mut args)
+        .and_then(|tracking| {
+            let (expanded, names) = expand_groups(&args, first, name)?;
+            let prelude = if args.prelude {
+                prelude_module(prelude_registrations(&args, &names))
+            } else {
+                TokenStream2::new()
+            };
+            Ok(quote!(#expanded #prelude #tracking))
+        })
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Apply `#[ext]` to every inherent impl in an inline module, sharing the given arguments.
+///
+/// An impl inside the module may still carry its own `#[ext(...)]`, whose arguments take
+/// precedence over the ones given to the module. Options that only make sense for a single block
+/// (like `also_for`, `newtype` or a trait name) can't be given to the module.
+/// An impl with `#[ext(append_to = Name)]` doesn't get its own trait, but adds its items to the
+/// trait of the earlier `#[ext(Name)]` block instead.
+/// `#[cfg_attr(predicate, ext(...))]` is supported as well: the generated trait and impl are put
+/// under `#[cfg(predicate)]` and the inherent impl is kept as it is otherwise.
+#[proc_macro_attribute]
+pub fn ext_mod(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as ExtArgs);
-    let name = args.trait_ident(input_hash);
+    let module = parse_macro_input!(input as ItemMod);
+
+    expand_mod(args, module)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_attr, ExtArgs};
+    use proc_macro2::TokenStream as TokenStream2;
+    use quote::{format_ident, quote};
+    use std::time::{Duration, Instant};
 
-    process_impl::make_trait_impl(&mut item, ident_to_path(name.clone()));
-    process_impl::copy_appropriate_where_clause_type_from_and_to_self(&mut item);
+    /// An impl with `n` constants and `n` methods (every other one a default, all with trait
+    /// docs), and a type parameter for every 8 items, which is bounded in the where clause
+    fn big_impl(n: usize) -> TokenStream2 {
+        let params: Vec<_> = (0..n / 8 + 1).map(|i| format_ident!("T{}", i)).collect();
+        let items = (0..n).map(|i| {
+            let method = format_ident!("m{}", i);
+            let constant = format_ident!("C{}", i);
+            let param = &params[i % params.len()];
+            let default = if i % 2 == 0 {
+                quote!(#[ext(default)])
+            } else {
+                quote!()
+            };
+            quote! {
+                /// Docs that have to be copied
+                #[ext(trait_doc = "Docs for the trait")]
+                #default
+                fn #method(&self, x: #param) -> Vec<#param> {
+                    vec![x; Self::#constant]
+                }
 
-    let trait_def = impl_to_trait::to_trait(item.clone(), args.vis, name);
+                const #constant: usize = #i;
+            }
+        });
+        quote! {
+            impl<#(#params),*> Big<#(#params),*>
+            where
+                #(#params: Clone + Default,)*
+            {
+                #(#items)*
+            }
+        }
+    }
 
-    quote!(#trait_def #item).into()
+    #[test]
+    fn trait_docs() {
+        let args: ExtArgs = syn::parse_quote!(Contracted);
+        let out = expand_attr(
+            args,
+            quote! {
+                impl Vec<u8> {
+                    /// Pushes into the spare capacity
+                    #[ext(trait_doc = " Must not allocate")]
+                    fn push_within(&mut self, b: u8) -> bool {
+                        false
+                    }
+                }
+            },
+        )
+        .to_string();
+        let (trait_def, impl_) = out.split_at(out.find("impl").unwrap());
+        assert!(trait_def.contains("Must not allocate"), "{}", out);
+        assert!(!trait_def.contains("spare"), "{}", out);
+        assert!(impl_.contains("Pushes into the spare capacity"), "{}", out);
+    }
+
+    /// How long `#[ext]` takes for `big_impl(n)`, at best
+    fn time(n: usize) -> Duration {
+        let input = big_impl(n);
+        (0..5)
+            .map(|_| {
+                let args: ExtArgs = syn::parse_quote!(pub BigExt, per_method_bounds, sorted);
+                let start = Instant::now();
+                let out = expand_attr(args, input.clone());
+                let elapsed = start.elapsed();
+                assert!(!out.to_string().contains("compile_error"), "{}", out);
+                elapsed
+            })
+            .min()
+            .unwrap()
+    }
+
+    /// A benchmark for large impls: the time per item should stay about the same as `n` grows
+    ///
+    /// Run it with `cargo test --release -- --ignored --nocapture large_impls`.
+    #[test]
+    #[ignore]
+    fn large_impls() {
+        for n in [100, 200, 400, 800, 1600] {
+            let t = time(n);
+            println!(
+                "{:>5} items: {:>9.2?} ({:.2?} per item)",
+                n,
+                t,
+                t / n as u32
+            );
+        }
+    }
 }
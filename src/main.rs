@@ -1,6 +1,6 @@
 use ext_trait::ext;
 
-#[ext]
+#[ext(VecExt)]
 impl<'a, T: Eq> Vec<&'a T>
 where
     T: std::fmt::Debug,
@@ -0,0 +1,307 @@
+use super::args::ExtArgs;
+use proc_macro2::{Ident, Span};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    GenericArgument, Generics, ItemImpl, PathArguments, Token, Type, TypeParamBound, WherePredicate,
+};
+
+fn capitalized(s: &str) -> String {
+    let s = s.trim_start_matches("r#");
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// How types are flattened into names (the argument of `type_name(...)`)
+///
+/// Lifetimes and const arguments are always left out.
+#[derive(Clone, Copy, Default)]
+pub struct NameStyle {
+    /// `StrRef` instead of `RefStr` (same for pointers, slices and arrays)
+    pub wrappers_last: bool,
+    /// `Vec` instead of `VecU8`
+    pub omit_generics: bool,
+    /// `StdVecVec` instead of `Vec`
+    pub full_paths: bool,
+}
+
+impl Parse for NameStyle {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut style = NameStyle::default();
+        let entries =
+            Punctuated::<(Ident, Ident), Token![,]>::parse_terminated_with(input, |input| {
+                let key = input.parse()?;
+                input.parse::<Token![=]>()?;
+                Ok((key, input.parse()?))
+            })?;
+        for (key, value) in entries {
+            let (flag, choices) = match key.to_string().as_str() {
+                "wrappers" => (&mut style.wrappers_last, ["first", "last"]),
+                "generics" => (&mut style.omit_generics, ["include", "omit"]),
+                "paths" => (&mut style.full_paths, ["last", "full"]),
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "expected `wrappers`, `generics` or `paths`",
+                    ))
+                }
+            };
+            *flag = match choices.iter().position(|c| value == c) {
+                Some(i) => i == 1,
+                None => {
+                    return Err(syn::Error::new(
+                        value.span(),
+                        format!("expected `{}` or `{}`", choices[0], choices[1]),
+                    ))
+                }
+            };
+        }
+        Ok(style)
+    }
+}
+
+fn push_path_name(out: &mut String, path: &syn::Path, style: NameStyle) {
+    let segments = if style.full_paths {
+        path.segments.len()
+    } else {
+        1
+    };
+    for seg in path
+        .segments
+        .iter()
+        .skip(path.segments.len().saturating_sub(segments))
+    {
+        out.push_str(&capitalized(&seg.ident.to_string()));
+    }
+    if let (Some(seg), false) = (path.segments.last(), style.omit_generics) {
+        if let PathArguments::AngleBracketed(a) = &seg.arguments {
+            for arg in &a.args {
+                if let GenericArgument::Type(t) = arg {
+                    push_type_name(out, t, style);
+                }
+            }
+        }
+    }
+}
+
+/// A wrapper type like a reference, e.g. `&str` => `RefStr` (or `StrRef`)
+fn push_wrapped(out: &mut String, wrapper: &str, elem: &Type, style: NameStyle) {
+    if !style.wrappers_last {
+        out.push_str(wrapper);
+    }
+    push_type_name(out, elem, style);
+    if style.wrappers_last {
+        out.push_str(wrapper);
+    }
+}
+
+fn push_type_name(out: &mut String, ty: &Type, style: NameStyle) {
+    match ty {
+        Type::Path(p) => push_path_name(out, &p.path, style),
+        Type::Reference(r) => {
+            let wrapper = if r.mutability.is_some() {
+                "RefMut"
+            } else {
+                "Ref"
+            };
+            push_wrapped(out, wrapper, &r.elem, style);
+        }
+        Type::Ptr(p) => push_wrapped(out, "Ptr", &p.elem, style),
+        Type::Slice(s) => push_wrapped(out, "Slice", &s.elem, style),
+        Type::Array(a) => push_wrapped(out, "Array", &a.elem, style),
+        Type::Tuple(t) if t.elems.is_empty() => out.push_str("Unit"),
+        Type::Tuple(t) => {
+            out.push_str("Tuple");
+            t.elems.iter().for_each(|t| push_type_name(out, t, style));
+        }
+        Type::TraitObject(t) => {
+            out.push_str("Dyn");
+            for b in &t.bounds {
+                if let syn::TypeParamBound::Trait(t) = b {
+                    push_path_name(out, &t.path, style);
+                }
+            }
+        }
+        Type::Paren(p) => push_type_name(out, &p.elem, style),
+        Type::Group(g) => push_type_name(out, &g.elem, style),
+        _ => out.push_str("Type"),
+    }
+}
+
+/// Flatten a type into something usable inside an identifier, e.g. `Vec<u8>` => `VecU8`
+pub fn type_name(ty: &Type, style: NameStyle) -> String {
+    let mut out = String::new();
+    push_type_name(&mut out, ty, style);
+    out
+}
+
+/// The first trait bound of `param`, either inline or in the where clause
+pub fn first_bound<'a>(param: &Ident, generics: &'a Generics) -> Option<&'a syn::Path> {
+    let inline = generics
+        .type_params()
+        .filter(|p| p.ident == *param)
+        .flat_map(|p| &p.bounds);
+    let in_where = generics
+        .where_clause
+        .iter()
+        .flat_map(|c| &c.predicates)
+        .filter_map(|p| match p {
+            WherePredicate::Type(t) => Some(t),
+            _ => None,
+        })
+        .filter(|t| matches!(&t.bounded_ty, Type::Path(p) if p.qself.is_none() && p.path.is_ident(param)))
+        .flat_map(|t| &t.bounds);
+    inline.chain(in_where).find_map(|b| match b {
+        TypeParamBound::Trait(t) => Some(&t.path),
+        _ => None,
+    })
+}
+
+/// Turns generic arguments that name const parameters (which syn can't tell apart from types)
+/// into const arguments, so that they're left out of names like other const arguments
+struct ConstArgs<'a> {
+    params: Vec<&'a Ident>,
+}
+
+impl VisitMut for ConstArgs<'_> {
+    fn visit_generic_argument_mut(&mut self, arg: &mut GenericArgument) {
+        if let GenericArgument::Type(Type::Path(p)) = arg {
+            if let (None, Some(ident)) = (&p.qself, p.path.get_ident()) {
+                if self.params.contains(&ident) {
+                    let ident = ident.clone();
+                    *arg = GenericArgument::Const(syn::parse_quote!(#ident));
+                    return;
+                }
+            }
+        }
+        visit_mut::visit_generic_argument_mut(self, arg);
+    }
+}
+
+/// The self type as used in names: for blanket impls like `impl<I: Iterator> I`, that's the
+/// first bound (`Iterator`), since the name of the parameter doesn't say anything
+pub fn self_name(self_ty: &Type, generics: &Generics, style: NameStyle) -> String {
+    if let Type::Path(p) = self_ty {
+        if let (None, Some(param)) = (&p.qself, p.path.get_ident()) {
+            if let Some(bound) = first_bound(param, generics) {
+                let mut out = String::new();
+                push_path_name(&mut out, bound, style);
+                return out;
+            }
+        }
+    }
+    let mut self_ty = self_ty.clone();
+    ConstArgs {
+        params: generics.const_params().map(|p| &p.ident).collect(),
+    }
+    .visit_type_mut(&mut self_ty);
+    type_name(&self_ty, style)
+}
+
+/// Expand a naming template like `"{type}Ext"`
+fn expand_template(template: &syn::LitStr, self_name: &str) -> syn::Result<Ident> {
+    let name = template.value().replace("{type}", self_name);
+    syn::parse_str::<Ident>(&name)
+        .map(|mut i| {
+            i.set_span(Span::call_site());
+            i
+        })
+        .map_err(|_| {
+            syn::Error::new(
+                template.span(),
+                format!("`{}` is not a valid trait name", name),
+            )
+        })
+}
+
+/// The name of an auto-named trait, e.g. `__VecU8ExtTrait_0123456789abcdef`
+///
+/// The self type (see `self_name`) is only there to make error messages easier to read. It always
+/// uses the default `NameStyle`.
+///
+/// The name has `Span::mixed_site()` hygiene, like an item that a `macro_rules!` macro defines.
+pub fn hashed_ident(self_name: &str, hash: u64) -> Ident {
+    Ident::new(
+        &format!("__{}ExtTrait_{:016x}", self_name, hash),
+        Span::mixed_site(),
+    )
+}
+
+/// The name of the trait for a group of items, e.g. `FooExt` and `"read"` => `FooReadExt`
+pub fn group_ident(base: &Ident, group: &str) -> Ident {
+    let span = base.span();
+    // the result is never a keyword, so it doesn't need to stay raw
+    let base = base.unraw().to_string();
+    let group: String = group.split('_').map(capitalized).collect();
+    let name = match base.strip_suffix("Ext") {
+        Some(stem) => format!("{}{}Ext", stem, group),
+        None => format!("{}{}", base, group),
+    };
+    Ident::new(&name, span)
+}
+
+/// The name of the trait for the `&mut self` methods, e.g. `FooExt` => `FooExtMut`
+pub fn mut_ident(base: &Ident) -> Ident {
+    Ident::new(&format!("{}Mut", base.unraw()), base.span())
+}
+
+/// The name of the trait for the methods that take `self`, e.g. `FooExt` => `IntoFooExt`
+pub fn consuming_ident(base: &Ident) -> Ident {
+    Ident::new(&format!("Into{}", base.unraw()), base.span())
+}
+
+/// The name of the macro behind `#[ext_delegate]`, e.g. `FooExt` => `__ext_delegate_FooExt`
+pub fn delegate_ident(base: &Ident) -> Ident {
+    Ident::new(&format!("__ext_delegate_{}", base.unraw()), base.span())
+}
+
+/// The name of the macro of `#[ext_decl]`, e.g. `FooExt` => `impl_foo_ext`
+pub fn decl_macro_ident(base: &Ident) -> Ident {
+    let chars: Vec<char> = base.unraw().to_string().chars().collect();
+    let mut name = String::from("impl");
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1);
+        // a word starts after a lowercase letter and at the last capital of an acronym
+        let starts_word = i == 0
+            || c.is_uppercase()
+                && (matches!(prev, Some(p) if !p.is_uppercase())
+                    || matches!(next, Some(n) if n.is_lowercase()));
+        if starts_word && !name.ends_with('_') {
+            name.push('_');
+        }
+        name.extend(c.to_lowercase());
+    }
+    Ident::new(&name, base.span())
+}
+
+/// Determine the name of the generated trait
+///
+/// The input is only hashed if it actually has to be
+pub fn trait_ident(
+    args: &ExtArgs,
+    item: &ItemImpl,
+    input_hash: impl FnOnce() -> u64,
+) -> syn::Result<Ident> {
+    if let Some(ident) = &args.ident {
+        Ok(ident.clone())
+    } else if let Some(template) = &args.name_template {
+        let self_name = self_name(&item.self_ty, &item.generics, args.name_style);
+        expand_template(template, &self_name)
+    } else if cfg!(feature = "require_names") {
+        Err(syn::Error::new(
+            item.impl_token.span,
+            "this trait needs a name (the `require_names` feature of ext_trait is enabled)",
+        ))
+    } else if let Some(id) = &args.id {
+        Ok(hashed_ident("", super::hash(&id.value())))
+    } else {
+        let self_name = self_name(&item.self_ty, &item.generics, NameStyle::default());
+        Ok(hashed_ident(&self_name, input_hash()))
+    }
+}
@@ -1,12 +1,24 @@
-use super::{ident_to_path, Token};
-use proc_macro2::{Ident, Span};
+use super::args::Msrv;
+use super::item_args::{impl_item_ident, trait_item_ident};
+use super::{ident_to_path, tokens_eq, tokens_key, Token};
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream as TokenStream2, TokenTree};
 use quote::ToTokens;
+use std::collections::{HashMap, HashSet};
 use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
 use syn::{
-    AngleBracketedGenericArguments, Expr, ExprPath, GenericArgument, GenericParam, ImplItem,
-    ItemImpl, Path, PathArguments, Type, TypePath, Visibility, WhereClause, WherePredicate,
+    AngleBracketedGenericArguments, Expr, ExprPath, FnArg, GenericArgument, GenericParam, Generics,
+    ImplItem, ItemImpl, ItemTrait, Path, PathArguments, PredicateType, Receiver, ReturnType,
+    TraitBoundModifier, TraitItem, Type, TypeParamBound, TypePath, Visibility, WhereClause,
+    WherePredicate,
 };
 
+/// Whether the receiver is `self` or `self: Self`, which takes `Self` by value
+pub fn by_value(r: &Receiver) -> bool {
+    r.reference.is_none()
+        && matches!(&*r.ty, Type::Path(p) if p.qself.is_none() && p.path.is_ident("Self"))
+}
+
 fn convert_generic_param_to_args(p: GenericParam) -> GenericArgument {
     match p {
         GenericParam::Type(t) => GenericArgument::Type(Type::Path(TypePath {
@@ -22,23 +34,31 @@ fn convert_generic_param_to_args(p: GenericParam) -> GenericArgument {
     }
 }
 
-/// Make the inherent impl a trait impl
-pub fn make_trait_impl(item: &mut ItemImpl, mut trait_ident_path: Path) {
-    // remove any `pub`
-    for ii in &mut item.items {
+/// Remove any `pub` from the items, which isn't allowed in trait impls
+pub fn strip_visibility(items: &mut [ImplItem]) {
+    for ii in items {
         match ii {
             ImplItem::Type(t) => t.vis = Visibility::Inherited,
             ImplItem::Const(c) => c.vis = Visibility::Inherited,
-            ImplItem::Method(m) => m.vis = Visibility::Inherited,
+            ImplItem::Fn(m) => m.vis = Visibility::Inherited,
             ImplItem::Macro(_) | ImplItem::Verbatim(_) => (),
             _ => unimplemented!("Unsupported item: {}", ii.to_token_stream()),
         }
     }
+}
+
+/// Make the inherent impl a trait impl
+pub fn make_trait_impl(item: &mut ItemImpl, mut trait_ident_path: Path) {
+    strip_visibility(&mut item.items);
 
     // insert the proper generic args
     // (the trait has all generic params too, i.e. `T<A, B>`, so we have to `impl<A, B> T<A, B> for ...`
     // the `<A, B>` from the `T<A, B>` in that last part is what is added here
-    if let Some(s) = trait_ident_path.segments.last_mut() {
+    // (nothing is added for impls without generic params, which would give `T<>`)
+    if let (Some(s), false) = (
+        trait_ident_path.segments.last_mut(),
+        item.generics.params.is_empty(),
+    ) {
         s.arguments = PathArguments::AngleBracketed(AngleBracketedGenericArguments {
             colon2_token: None,
             lt_token: item
@@ -64,7 +84,7 @@ pub fn make_trait_impl(item: &mut ItemImpl, mut trait_ident_path: Path) {
 }
 
 fn where_predicate_from_take_generic_bounds(g: &mut GenericParam) -> Option<WherePredicate> {
-    use syn::{PredicateLifetime, PredicateType};
+    use syn::PredicateLifetime;
 
     match g {
         GenericParam::Type(t) => {
@@ -118,33 +138,660 @@ pub fn move_bounds_to_where_clause(item: &mut ItemImpl) {
     }
 }
 
+/// Replaces every occurrence of one type (however deeply nested) with another
+struct ReplaceType<'a> {
+    from: &'a Type,
+    /// `from` spelled out once, since it's compared with every type that is visited
+    from_tokens: String,
+    to: &'a Type,
+    replaced: bool,
+}
+
+impl<'a> ReplaceType<'a> {
+    fn new(from: &'a Type, to: &'a Type) -> Self {
+        ReplaceType {
+            from,
+            from_tokens: tokens_key(from),
+            to,
+            replaced: false,
+        }
+    }
+
+    fn is_from(&self, ty: &Type) -> bool {
+        !differ_in_kind(ty, self.from) && tokens_key(ty) == self.from_tokens
+    }
+}
+
+/// The type without the invisible groups around it
+fn ungrouped(ty: &Type) -> &Type {
+    match ty {
+        Type::Group(g) => ungrouped(&g.elem),
+        ty => ty,
+    }
+}
+
+/// Whether the types are of different kinds (or paths to different things), which is much cheaper
+/// to tell than whether they are equal
+fn differ_in_kind(a: &Type, b: &Type) -> bool {
+    match (ungrouped(a), ungrouped(b)) {
+        (Type::Path(a), Type::Path(b)) => {
+            let last = |p: &TypePath| p.path.segments.last().map(|s| s.ident.clone());
+            last(a) != last(b)
+        }
+        (a, b) => std::mem::discriminant(a) != std::mem::discriminant(b),
+    }
+}
+
+impl VisitMut for ReplaceType<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if self.is_from(ty) {
+            *ty = self.to.clone();
+            self.replaced = true;
+        } else {
+            visit_mut::visit_type_mut(self, ty);
+        }
+    }
+}
+
+/// A copy of the predicate with `from` replaced by `to`, if `from` appears in it at all (in the
+/// bounded type or in the arguments of the bounds)
+fn replaced(p: &PredicateType, visitor: &mut ReplaceType) -> Option<PredicateType> {
+    let mut p = p.clone();
+    visitor.replaced = false;
+    visitor.visit_predicate_type_mut(&mut p);
+    Some(p).filter(|_| visitor.replaced)
+}
+
+fn self_type() -> Type {
+    Type::Path(TypePath {
+        qself: None,
+        path: ident_to_path(Ident::new("Self", Span::call_site())),
+    })
+}
+
+/// Replace `Self` in the type
+pub fn replace_self(ty: &mut Type, with: &Type) {
+    ReplaceType::new(&self_type(), with).visit_type_mut(ty);
+}
+
+/// Replaces the self type by `Self`, both as a type and as the prefix of paths to associated items
+/// (like `Vec::<u8>::new`)
+struct SelfTypeToSelf<'a> {
+    self_ty: &'a Type,
+}
+
+impl VisitMut for SelfTypeToSelf<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if tokens_eq(ty, self.self_ty) {
+            *ty = self_type();
+        } else {
+            visit_mut::visit_type_mut(self, ty);
+        }
+    }
+
+    fn visit_expr_path_mut(&mut self, e: &mut ExprPath) {
+        let n = e.path.segments.len();
+        if e.qself.is_none() && n >= 2 {
+            // in expressions, generic arguments need a turbofish, which types don't have
+            let mut prefix = e.path.clone();
+            prefix.segments = prefix.segments.into_iter().take(n - 1).collect();
+            for segment in &mut prefix.segments {
+                if let PathArguments::AngleBracketed(a) = &mut segment.arguments {
+                    a.colon2_token = None;
+                }
+            }
+            let prefix = Type::Path(TypePath {
+                qself: None,
+                path: prefix,
+            });
+            if tokens_eq(&prefix, self.self_ty) {
+                let last = e.path.segments.last().unwrap().clone();
+                e.path = ident_to_path(Ident::new("Self", Span::call_site()));
+                e.path.segments.push(last);
+                return;
+            }
+        }
+        visit_mut::visit_expr_path_mut(self, e);
+    }
+}
+
+/// Unwraps types from invisible groups where the group doesn't matter for how they're parsed
+struct UnwrapTypeGroups;
+
+impl VisitMut for UnwrapTypeGroups {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        visit_mut::visit_type_mut(self, ty);
+        if let Type::Group(g) = ty {
+            // e.g. `&$t` with `dyn A + B` for `$t` needs the group
+            if !matches!(
+                *g.elem,
+                Type::TraitObject(_) | Type::ImplTrait(_) | Type::BareFn(_)
+            ) {
+                *ty = (*g.elem).clone();
+            }
+        }
+    }
+}
+
+/// Take the types that `macro_rules!` metavariables like `$t:ty` stand for out of the invisible
+/// groups they come in, so that they look like they were written directly (e.g. to be recognized
+/// as `str` or as a type parameter)
+pub fn unwrap_type_groups(item: &mut ItemImpl) {
+    UnwrapTypeGroups.visit_item_impl_mut(item);
+}
+
+/// The placeholder for the self type of each impl, for bodies shared through `also_for`
+const TARGET_PLACEHOLDER: &str = "__Target";
+
+/// Replace the placeholder in `ts` with `ty`, or `<ty>` when it starts a path like `__Target::MAX`
+fn substitute_target_tokens(ts: TokenStream2, ty: &Type) -> TokenStream2 {
+    let tokens: Vec<TokenTree> = ts.into_iter().collect();
+    let mut out = TokenStream2::new();
+    for (i, tt) in tokens.iter().enumerate() {
+        match tt {
+            TokenTree::Ident(id) if id == TARGET_PLACEHOLDER => {
+                let starts_path = matches!(
+                    (tokens.get(i + 1), tokens.get(i + 2)),
+                    (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b)))
+                        if a.as_char() == ':' && b.as_char() == ':'
+                );
+                if starts_path {
+                    out.extend(quote::quote!(<#ty>));
+                } else {
+                    let mut group = Group::new(Delimiter::None, ty.to_token_stream());
+                    group.set_span(id.span());
+                    out.extend(Some(TokenTree::Group(group)));
+                }
+            }
+            TokenTree::Group(g) => {
+                let mut new = Group::new(g.delimiter(), substitute_target_tokens(g.stream(), ty));
+                new.set_span(g.span());
+                out.extend(Some(TokenTree::Group(new)));
+            }
+            tt => out.extend(Some(tt.clone())),
+        }
+    }
+    out
+}
+
+/// Replace `__Target` with the self type in the bodies, constant values and associated types
+/// of the impl, including inside macro invocations
+pub fn substitute_target(item: &mut ItemImpl) -> syn::Result<()> {
+    let ty = &*item.self_ty;
+    for ii in &mut item.items {
+        match ii {
+            ImplItem::Fn(m) => {
+                m.block = syn::parse2(substitute_target_tokens(m.block.to_token_stream(), ty))?;
+            }
+            ImplItem::Const(c) => {
+                c.expr = syn::parse2(substitute_target_tokens(c.expr.to_token_stream(), ty))?;
+            }
+            ImplItem::Type(t) => {
+                t.ty = syn::parse2(substitute_target_tokens(t.ty.to_token_stream(), ty))?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Spell the self type as `Self` in the item, to make its body work as a default for other types
+///
+/// Only the self type exactly as it's written in the impl is replaced, so e.g. `Vec::new()` stays
+/// as it is if the self type is `Vec<u8>`, and macro invocations aren't looked into.
+pub fn self_type_to_self(item: &mut ImplItem, self_ty: &Type) {
+    SelfTypeToSelf { self_ty }.visit_impl_item_mut(item);
+}
+
+/// Spell the self type as `Self` in the where clause of the trait (given by its generics) and
+/// `Self` as the self type in the where clause of the impl
+pub fn split_self_predicates(trait_generics: &mut Generics, item: &mut ItemImpl) {
+    let self_ = self_type();
+    let mut to_self = ReplaceType::new(&item.self_ty, &self_);
+    if let Some(c) = &mut trait_generics.where_clause {
+        to_self.visit_where_clause_mut(c);
+    }
+    let mut from_self = ReplaceType::new(&self_, &item.self_ty);
+    if let Some(c) = &mut item.generics.where_clause {
+        from_self.visit_where_clause_mut(c);
+    }
+}
+
 pub fn copy_appropriate_where_clause_type_from_and_to_self(item: &mut ItemImpl) {
+    let self_ = self_type();
+    let self_ty = &*item.self_ty;
+
     if let Some(c) = &mut item.generics.where_clause {
         let mut extra = Punctuated::<WherePredicate, Token![,]>::new();
+        let mut to_self = ReplaceType::new(self_ty, &self_);
+        let mut from_self = ReplaceType::new(&self_, self_ty);
 
-        for p in c.predicates.iter_mut() {
+        for p in c.predicates.iter() {
             if let WherePredicate::Type(t) = p {
-                if t.bounded_ty == *item.self_ty {
-                    // make a copy and change the bounded type to `Self`
-                    let mut t = t.clone();
-                    t.bounded_ty = Type::Path(TypePath {
-                        qself: None,
-                        path: ident_to_path(Ident::new("Self", Span::call_site())),
-                    });
-                    extra.push(WherePredicate::Type(t));
-                } else if let Type::Path(p) = &mut t.bounded_ty {
-                    if let Some(seg) = p.path.segments.last_mut() {
-                        if seg.ident == "Self" {
-                            // make a copy and change the bounded type to the other form of `Self`
-                            let mut t = t.clone();
-                            t.bounded_ty = (*item.self_ty).clone();
-                            extra.push(WherePredicate::Type(t));
-                        }
+                // make copies with the other form of `Self` (wherever it is)
+                let twins = replaced(t, &mut to_self)
+                    .into_iter()
+                    .chain(replaced(t, &mut from_self));
+                extra.extend(twins.map(WherePredicate::Type));
+            }
+        }
+
+        c.predicates.extend(extra);
+        dedup_predicates(c);
+    }
+}
+
+/// Remove predicates that are equal to an earlier one (e.g. `T: A` written both inline and in the where clause)
+fn dedup_predicates(c: &mut WhereClause) {
+    let mut seen = HashSet::new();
+    let predicates = std::mem::take(&mut c.predicates);
+    c.predicates = predicates
+        .into_iter()
+        .filter(|p| seen.insert(p.to_token_stream().to_string()))
+        .collect();
+}
+
+/// Whether `Self` appears somewhere it has to be `Sized`, e.g. in `Vec<Self>` (but not in `&Self`
+/// or as a whole argument or return type, which are fine in a trait)
+fn needs_sized_self(ty: &Type, nested: bool) -> bool {
+    match ty {
+        Type::Path(p) if p.qself.is_none() && p.path.is_ident("Self") => nested,
+        Type::Path(p) => p.path.segments.iter().any(|s| match &s.arguments {
+            PathArguments::AngleBracketed(a) => a.args.iter().any(|arg| match arg {
+                GenericArgument::Type(t) => needs_sized_self(t, true),
+                GenericArgument::AssocType(b) => needs_sized_self(&b.ty, true),
+                _ => false,
+            }),
+            PathArguments::Parenthesized(_) | PathArguments::None => false,
+        }),
+        Type::Reference(r) => needs_sized_self(&r.elem, false),
+        Type::Ptr(p) => needs_sized_self(&p.elem, false),
+        Type::Slice(s) => needs_sized_self(&s.elem, true),
+        Type::Array(a) => needs_sized_self(&a.elem, true),
+        Type::Tuple(t) => t.elems.iter().any(|t| needs_sized_self(t, true)),
+        Type::Paren(p) => needs_sized_self(&p.elem, nested),
+        Type::Group(g) => needs_sized_self(&g.elem, nested),
+        _ => false,
+    }
+}
+
+/// Whether the self type might not be `Sized`
+fn maybe_unsized(item: &ItemImpl) -> bool {
+    let is_maybe = |b: &TypeParamBound| matches!(b, TypeParamBound::Trait(t) if matches!(t.modifier, TraitBoundModifier::Maybe(_)));
+    match &*item.self_ty {
+        Type::Slice(_) | Type::TraitObject(_) => true,
+        Type::Path(p) if p.path.is_ident("str") => true,
+        Type::Path(p) if p.qself.is_none() => match p.path.get_ident() {
+            Some(param) => {
+                item.generics.type_params().any(|t| t.ident == *param && t.bounds.iter().any(is_maybe))
+                    || item.generics.where_clause.iter().flat_map(|c| &c.predicates).any(|wp| {
+                        matches!(wp, WherePredicate::Type(t)
+                            if tokens_eq(&t.bounded_ty, &*item.self_ty) && t.bounds.iter().any(is_maybe))
+                    })
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Add `where Self: Sized` to the methods whose signatures need it, if the self type is `Sized`
+/// anyway (which the trait doesn't know on its own)
+///
+/// Methods that become defaults (named in `defaulted`) also need it for taking or returning `Self`
+/// by value, since their bodies are checked in the trait.
+pub fn add_sized_bounds(item: &mut ItemImpl, defaulted: &HashSet<String>) {
+    if maybe_unsized(item) {
+        return;
+    }
+    for ii in &mut item.items {
+        if let ImplItem::Fn(m) = ii {
+            let default = defaulted.contains(&m.sig.ident.to_string());
+            let by_value = matches!(m.sig.receiver(), Some(r) if by_value(r));
+            let inputs = m.sig.inputs.iter().filter_map(|arg| match arg {
+                FnArg::Typed(t) => Some(&*t.ty),
+                FnArg::Receiver(_) => None,
+            });
+            let output = match &m.sig.output {
+                ReturnType::Type(_, ty) => Some(&**ty),
+                ReturnType::Default => None,
+            };
+            if (default && by_value) || inputs.chain(output).any(|ty| needs_sized_self(ty, default))
+            {
+                m.sig
+                    .generics
+                    .make_where_clause()
+                    .predicates
+                    .push(syn::parse_quote!(Self: Sized));
+            }
+        }
+    }
+}
+
+/// Whether the self type is never `Sized`
+pub fn is_unsized(ty: &Type) -> bool {
+    match ty {
+        Type::Slice(_) | Type::TraitObject(_) => true,
+        Type::Path(p) => p.qself.is_none() && p.path.is_ident("str"),
+        Type::Paren(p) => is_unsized(&p.elem),
+        Type::Group(g) => is_unsized(&g.elem),
+        _ => false,
+    }
+}
+
+/// Reject methods that take or return an unsized self type by value, which would otherwise lead to
+/// errors somewhere in the generated code
+pub fn check_unsized(item: &ItemImpl) -> syn::Result<()> {
+    if !is_unsized(&item.self_ty) {
+        return Ok(());
+    }
+    let is_self = |ty: &Type| match ty {
+        Type::Path(p) if p.qself.is_none() && p.path.is_ident("Self") => true,
+        ty => tokens_eq(ty, &*item.self_ty),
+    };
+    let error = |tokens: &dyn ToTokens, what: &str| {
+        syn::Error::new_spanned(
+            tokens,
+            format!(
+                "`{}` isn't `Sized`, so it can't be {} by value",
+                item.self_ty.to_token_stream(),
+                what
+            ),
+        )
+    };
+    for ii in &item.items {
+        let sig = match ii {
+            ImplItem::Fn(m) => &m.sig,
+            _ => continue,
+        };
+        for arg in &sig.inputs {
+            match arg {
+                FnArg::Receiver(r) if r.reference.is_none() && is_self(&r.ty) => {
+                    return Err(error(r, "taken"))
+                }
+                FnArg::Typed(t) if is_self(&t.ty) => return Err(error(t, "taken")),
+                _ => (),
+            }
+        }
+        if let ReturnType::Type(_, ty) = &sig.output {
+            if is_self(ty) {
+                return Err(error(ty, "returned"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces `Self::Assoc` with the definition of `Assoc`
+struct ResolveProjections<'a> {
+    types: &'a HashMap<Ident, Type>,
+}
+
+impl VisitMut for ResolveProjections<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::Path(p) = ty {
+            let segments = &p.path.segments;
+            if p.qself.is_none()
+                && segments.len() == 2
+                && segments[0].ident == "Self"
+                && segments.iter().all(|s| s.arguments.is_empty())
+            {
+                if let Some(def) = self.types.get(&segments[1].ident) {
+                    *ty = def.clone();
+                    return;
+                }
+            }
+        }
+        visit_mut::visit_type_mut(self, ty);
+    }
+}
+
+/// Spell `Self::Assoc` out in the where clause of the impl, for associated types defined in it
+///
+/// The trait can refer to its own associated types, but in the impl that would be a cycle.
+pub fn resolve_self_projections(item: &mut ItemImpl) {
+    let types: HashMap<Ident, Type> = item
+        .items
+        .iter()
+        .filter_map(|ii| match ii {
+            ImplItem::Type(t) if t.generics.params.is_empty() => {
+                Some((t.ident.clone(), t.ty.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    if let (Some(c), false) = (&mut item.generics.where_clause, types.is_empty()) {
+        ResolveProjections { types: &types }.visit_where_clause_mut(c);
+    }
+}
+
+/// All identifiers in the tokens, with `self` counted as `Self`
+fn collect_idents(ts: TokenStream2, out: &mut HashSet<String>) {
+    for tt in ts {
+        match tt {
+            TokenTree::Ident(i) if i == "self" => {
+                out.insert("Self".to_string());
+            }
+            TokenTree::Ident(i) => {
+                out.insert(i.to_string());
+            }
+            TokenTree::Group(g) => collect_idents(g.stream(), out),
+            _ => (),
+        }
+    }
+}
+
+/// Reject what `msrv` doesn't support and prepare `async fn`s for `async_trait` if they aren't
+/// supported in traits
+///
+/// Returns whether `#[async_trait::async_trait]` was added to the impl, which means that the trait
+/// needs it as well.
+pub fn apply_msrv(item: &mut ItemImpl, msrv: Msrv) -> syn::Result<bool> {
+    let too_old = |tokens: &dyn ToTokens, what: &str, needed: Msrv| {
+        syn::Error::new_spanned(
+            tokens,
+            format!("{} need Rust {}, but `msrv` is {}", what, needed, msrv),
+        )
+    };
+    let mut has_async = false;
+    for ii in &item.items {
+        match ii {
+            ImplItem::Type(t) if !t.generics.params.is_empty() && msrv < Msrv::GATS => {
+                return Err(too_old(&t.generics, "generic associated types", Msrv::GATS));
+            }
+            ImplItem::Fn(m) => {
+                if let ReturnType::Type(_, ty) = &m.sig.output {
+                    let idents = idents(ty);
+                    if idents.contains("impl") && msrv < Msrv::TRAIT_ASYNC_AND_IMPL {
+                        return Err(too_old(
+                            ty,
+                            "`impl Trait` return types in traits",
+                            Msrv::TRAIT_ASYNC_AND_IMPL,
+                        ));
+                    }
+                    if idents.contains("use") && msrv < Msrv::TRAIT_PRECISE_CAPTURES {
+                        return Err(too_old(
+                            ty,
+                            "`use<...>` bounds in traits",
+                            Msrv::TRAIT_PRECISE_CAPTURES,
+                        ));
                     }
                 }
+                has_async |= m.sig.asyncness.is_some() && msrv < Msrv::TRAIT_ASYNC_AND_IMPL;
+            }
+            _ => {}
+        }
+    }
+    let has_async_trait = item
+        .attrs
+        .iter()
+        .any(|a| matches!(a.path().segments.last(), Some(s) if s.ident == "async_trait"));
+    if has_async && !has_async_trait {
+        item.attrs
+            .push(syn::parse_quote!(#[async_trait::async_trait]));
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+pub fn idents(tokens: &impl ToTokens) -> HashSet<String> {
+    let mut out = HashSet::new();
+    collect_idents(tokens.to_token_stream(), &mut out);
+    out
+}
+
+/// Move the predicates of the trait's where clause onto the methods that mention the generic
+/// parameters (or `Self`) they bound, as far as that can be told from the impl
+///
+/// Predicates that (might) matter for associated types or constants stay where they are.
+pub fn per_method_bounds(trait_def: &mut ItemTrait, item: &ItemImpl) {
+    let mut params: HashSet<String> = item
+        .generics
+        .type_params()
+        .map(|p| p.ident.to_string())
+        .collect();
+    params.insert("Self".to_string());
+
+    // what the items of the trait mention, which is looked up by name in the impl (where the
+    // bodies are), except for items that aren't there (anymore), like defaults
+    let in_impl: HashMap<String, HashSet<String>> = item
+        .items
+        .iter()
+        .filter_map(|ii| Some((impl_item_ident(ii)?.to_string(), idents(ii))))
+        .collect();
+    // the positions of the items that mention each parameter, and whether any of them isn't a
+    // method (so that each predicate only has to look at the items it's about)
+    let mut mentioned_by: HashMap<&String, (Vec<usize>, bool)> = HashMap::new();
+    for (idx, ti) in trait_def.items.iter().enumerate() {
+        let own;
+        let mentioned = match trait_item_ident(ti).and_then(|i| in_impl.get(&i.to_string())) {
+            Some(mentioned) => mentioned,
+            None => {
+                own = idents(ti);
+                &own
             }
+        };
+        for param in mentioned.iter().filter_map(|i| params.get(i)) {
+            let (items, not_only_methods) = mentioned_by.entry(param).or_default();
+            items.push(idx);
+            *not_only_methods |= !matches!(ti, TraitItem::Fn(_));
         }
+    }
 
-        c.predicates.extend(extra);
+    let c = match &mut trait_def.generics.where_clause {
+        Some(c) => c,
+        None => return,
+    };
+    for p in std::mem::take(&mut c.predicates) {
+        let relevant: Vec<String> = match &p {
+            WherePredicate::Type(t) => idents(&t.bounded_ty)
+                .into_iter()
+                .filter(|i| params.contains(i))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let users: Vec<_> = relevant
+            .iter()
+            .filter_map(|i| mentioned_by.get(i))
+            .collect();
+        if relevant.is_empty() || users.iter().any(|(_, not_only_methods)| *not_only_methods) {
+            c.predicates.push(p);
+            continue;
+        }
+        let mut methods: Vec<usize> = users
+            .into_iter()
+            .flat_map(|(items, _)| items.iter().copied())
+            .collect();
+        methods.sort_unstable();
+        methods.dedup();
+        for idx in methods {
+            if let TraitItem::Fn(m) = &mut trait_def.items[idx] {
+                m.sig
+                    .generics
+                    .make_where_clause()
+                    .predicates
+                    .push(p.clone());
+            }
+        }
+    }
+    if c.predicates.is_empty() {
+        trait_def.generics.where_clause = None;
+    }
+}
+
+/// Drop the `const` of the methods, which are const implicitly in a `#[const_trait]` (and its
+/// `impl const`s), after checking that there are no other methods
+pub fn strip_constness(item: &mut ItemImpl) -> syn::Result<()> {
+    for ii in &mut item.items {
+        if let ImplItem::Fn(m) = ii {
+            if m.sig.constness.take().is_none() {
+                return Err(syn::Error::new(
+                    m.sig.ident.span(),
+                    "with `const`, all methods have to be `const fn`s",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The impl as `impl const Trait for Type`, which syn can't represent
+pub fn const_impl(item: &ItemImpl) -> TokenStream2 {
+    let ItemImpl {
+        attrs,
+        defaultness,
+        unsafety,
+        impl_token,
+        generics,
+        trait_,
+        self_ty,
+        items,
+        ..
+    } = item;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (bang, path, for_token) = trait_.as_ref().expect("the impl is a trait impl");
+    quote::quote! {
+        #(#attrs)*
+        #defaultness #unsafety #impl_token #impl_generics const #bang #path #for_token #self_ty
+            #where_clause
+        {
+            #(#items)*
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::make_trait_impl;
+    use quote::ToTokens;
+    use syn::ItemImpl;
+
+    fn trait_impl(item: &str) -> String {
+        let mut item: ItemImpl = syn::parse_str(item).unwrap();
+        make_trait_impl(&mut item, syn::parse_quote!(Ext));
+        item.into_token_stream().to_string()
+    }
+
+    /// The impl as it's printed after parsing it, which is how `trait_impl` prints as well
+    fn tokens(item: &str) -> String {
+        let item: ItemImpl = syn::parse_str(item).unwrap();
+        item.into_token_stream().to_string()
+    }
+
+    #[test]
+    fn trait_paths() {
+        assert_eq!(
+            trait_impl("impl u16 { pub fn f(&self) {} }"),
+            tokens("impl Ext for u16 { fn f(&self) {} }")
+        );
+        assert_eq!(
+            trait_impl("impl<T: Copy> [T] {}"),
+            tokens("impl<T: Copy> Ext<T> for [T] {}")
+        );
+        assert_eq!(
+            trait_impl("impl<'a, T, const N: usize> Foo<'a, T, N> {}"),
+            tokens("impl<'a, T, const N: usize> Ext<'a, T, N> for Foo<'a, T, N> {}")
+        );
     }
 }
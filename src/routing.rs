@@ -0,0 +1,194 @@
+use super::item_args::{impl_item_attrs_mut, trait_item_attrs_mut};
+use super::{tokens_eq, CfgAttr};
+use quote::quote;
+use syn::{Attribute, ImplItem, ItemImpl, Path, TraitItem};
+
+/// Built-in attributes that have the same meaning on the trait, which are copied to it; all
+/// others (attribute macros, helper attributes and anything else that rustc doesn't know) are left
+/// on the impl, where they were written
+const COPIED: &[&str] = &[
+    "doc",
+    "cfg",
+    "cfg_attr",
+    "allow",
+    "warn",
+    "deny",
+    "forbid",
+    "expect",
+    "must_use",
+    "deprecated",
+    "track_caller",
+];
+
+/// Tools whose attributes are inert, like `#[rustfmt::skip]`
+const TOOLS: &[&str] = &["rustfmt", "clippy", "rustdoc"];
+
+const LINT_ATTRS: &[&str] = &["allow", "warn", "deny", "forbid", "expect"];
+
+/// Which attributes stay on the impl, in addition to the ones that aren't built in
+#[derive(Clone, Default)]
+pub struct ImplOnly {
+    /// `allow`, `warn`, etc.
+    pub lints: bool,
+    pub paths: Vec<Path>,
+    /// Attributes that are copied to the trait even though they aren't built in, like
+    /// `async_trait`
+    pub copied: Vec<Path>,
+}
+
+impl ImplOnly {
+    pub fn add(&mut self, path: Path) {
+        if path.is_ident("lints") {
+            self.lints = true;
+        } else {
+            self.paths.push(path);
+        }
+    }
+
+    pub fn extend(&mut self, other: &ImplOnly) {
+        self.lints |= other.lints;
+        self.paths.extend(other.paths.iter().cloned());
+        self.copied.extend(other.copied.iter().cloned());
+    }
+
+    /// Whether an attribute with this path is copied to the trait (if it isn't listed with
+    /// `impl_only`)
+    fn is_copied(&self, path: &Path) -> bool {
+        let first = match path.segments.first() {
+            Some(s) => s.ident.to_string(),
+            None => return false,
+        };
+        let built_in = match path.segments.len() {
+            1 => COPIED.contains(&first.as_str()),
+            _ => TOOLS.contains(&first.as_str()),
+        };
+        built_in || self.copied.iter().any(|p| tokens_eq(p, path))
+    }
+
+    /// Whether an attribute with this path must stay on the impl and not be copied to the trait
+    ///
+    /// `cfg` never does, since the items of the trait and the impl have to be there under the
+    /// same conditions.
+    fn keeps(&self, path: &Path) -> bool {
+        if path.is_ident("cfg") {
+            return false;
+        }
+        let single = path.segments.len() == 1;
+        !self.is_copied(path)
+            || (self.lints && single && LINT_ATTRS.iter().any(|l| path.is_ident(l)))
+            || self.paths.iter().any(|p| tokens_eq(p, path))
+    }
+
+    /// The attribute as it's copied to the trait, if it is
+    ///
+    /// Of a `cfg_attr` that has to stay on the impl, e.g. because of an attribute macro in it
+    /// (`#[cfg_attr(test, faux::methods)]` is as much an attribute macro as the inner one), the
+    /// attributes that would be copied on their own still are, like a `cfg` next to the macro.
+    fn for_trait(&self, attr: Attribute) -> Option<Attribute> {
+        if !attr.path().is_ident("cfg_attr") {
+            return Some(attr).filter(|a| !self.keeps(a.path()));
+        }
+        if self.keeps(attr.path()) {
+            return None;
+        }
+        let cfg_attr: CfgAttr = attr.parse_args().ok()?;
+        if cfg_attr.attrs.iter().all(|(p, _)| !self.keeps(p)) {
+            return Some(attr);
+        }
+        let predicate = &cfg_attr.predicate;
+        let copied: Vec<_> = cfg_attr
+            .attrs
+            .iter()
+            .filter(|(p, _)| !self.keeps(p))
+            .map(|(p, ts)| quote!(#p #ts))
+            .collect();
+        if copied.is_empty() {
+            None
+        } else {
+            Some(syn::parse_quote!(#[cfg_attr(#predicate, #(#copied),*)]))
+        }
+    }
+
+    /// Remove the attributes that only belong on the impl
+    pub fn strip(&self, attrs: &mut Vec<Attribute>) {
+        *attrs = std::mem::take(attrs)
+            .into_iter()
+            .filter_map(|a| self.for_trait(a))
+            .collect();
+    }
+
+    pub fn strip_trait_item(&self, mut item: TraitItem) -> TraitItem {
+        if let Some(attrs) = trait_item_attrs_mut(&mut item) {
+            self.strip(attrs);
+        }
+        item
+    }
+}
+
+/// The `cfg`s among the attributes of an item (including those in `cfg_attr`s), for code that is
+/// generated for the item and has to be there under the same conditions
+pub fn item_cfgs(attrs: &[Attribute]) -> Vec<Attribute> {
+    attrs
+        .iter()
+        .filter_map(|a| {
+            if a.path().is_ident("cfg") {
+                return Some(a.clone());
+            }
+            if !a.path().is_ident("cfg_attr") {
+                return None;
+            }
+            let cfg_attr: CfgAttr = a.parse_args().ok()?;
+            let predicate = &cfg_attr.predicate;
+            let cfgs: Vec<_> = cfg_attr
+                .attrs
+                .iter()
+                .filter(|(p, _)| p.is_ident("cfg"))
+                .map(|(p, ts)| quote!(#p #ts))
+                .collect();
+            if cfgs.is_empty() {
+                None
+            } else {
+                Some(syn::parse_quote!(#[cfg_attr(#predicate, #(#cfgs),*)]))
+            }
+        })
+        .collect()
+}
+
+/// Attributes that only affect documentation (which is read on the trait)
+const PRESENTATION_ONLY: &[&str] = &["doc"];
+
+/// Remove the attributes that don't do anything on the impl as long as the trait has them
+pub fn slim_impl(item: &mut ItemImpl) {
+    let keep = |a: &Attribute| !PRESENTATION_ONLY.iter().any(|p| a.path().is_ident(p));
+    item.attrs.retain(keep);
+    for ii in &mut item.items {
+        if let Some(attrs) = impl_item_attrs_mut(ii) {
+            attrs.retain(keep);
+        }
+    }
+}
+
+/// Attributes that only have an effect on the trait (and that rustc rejects on trait impls)
+const TRAIT_ONLY: &[&str] = &["deprecated", "must_use"];
+
+/// Remove the attributes that only belong on the trait
+pub fn strip_trait_only(item: &mut ItemImpl) {
+    let keep = |a: &Attribute| !TRAIT_ONLY.iter().any(|p| a.path().is_ident(p));
+    item.attrs.retain(keep);
+    for ii in &mut item.items {
+        if let Some(attrs) = impl_item_attrs_mut(ii) {
+            attrs.retain(keep);
+        }
+    }
+}
+
+/// Mark every method of the impl `#[inline]` (unless it already has an `#[inline(...)]`)
+pub fn inline_all(item: &mut ItemImpl) {
+    for ii in &mut item.items {
+        if let ImplItem::Fn(m) = ii {
+            if !m.attrs.iter().any(|a| a.path().is_ident("inline")) {
+                m.attrs.push(syn::parse_quote!(#[inline]));
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::{ImplItem, ItemImpl};
+
+/// Methods of prominent std traits, along with the trait
+const STD_METHODS: &[(&str, &str)] = &[
+    ("as_mut", "AsMut"),
+    ("as_ref", "AsRef"),
+    ("borrow", "Borrow"),
+    ("borrow_mut", "BorrowMut"),
+    ("clone", "Clone"),
+    ("clone_from", "Clone"),
+    ("cmp", "Ord"),
+    ("default", "Default"),
+    ("deref", "Deref"),
+    ("deref_mut", "DerefMut"),
+    ("eq", "PartialEq"),
+    ("extend", "Extend"),
+    ("fmt", "Debug` and `Display"),
+    ("from", "From"),
+    ("from_iter", "FromIterator"),
+    ("from_str", "FromStr"),
+    ("hash", "Hash"),
+    ("into", "Into"),
+    ("into_iter", "IntoIterator"),
+    ("max", "Ord"),
+    ("min", "Ord"),
+    ("ne", "PartialEq"),
+    ("next", "Iterator"),
+    ("partial_cmp", "PartialOrd"),
+    ("to_owned", "ToOwned"),
+    ("to_string", "ToString"),
+    ("try_from", "TryFrom"),
+    ("try_into", "TryInto"),
+];
+
+/// Warnings for methods that have the name of a std trait method
+///
+/// Proc macros can't emit warnings on stable, so this calls a deprecated function instead.
+pub fn check(item: &ItemImpl) -> TokenStream2 {
+    let mut out = TokenStream2::new();
+    for ii in &item.items {
+        let ident = match ii {
+            ImplItem::Fn(m) => &m.sig.ident,
+            _ => continue,
+        };
+        let name = ident.unraw().to_string();
+        if let Some((_, tr)) = STD_METHODS.iter().find(|(m, _)| *m == name) {
+            let note = format!(
+                "`{}` has the same name as a method of `{}`, which makes method resolution confusing",
+                name, tr
+            );
+            let warning = format_ident!("__ext_trait_shadows_{}", name);
+            // the warning points at the method name
+            let call_warning = format_ident!("{}", warning, span = ident.span());
+            let call = quote_spanned!(ident.span()=> #call_warning());
+            out.extend(quote! {
+                const _: () = {
+                    #[deprecated(note = #note)]
+                    #[allow(non_snake_case)]
+                    fn #warning() {}
+                    fn __check() {
+                        #call
+                    }
+                };
+            });
+        }
+    }
+    out
+}
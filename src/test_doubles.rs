@@ -0,0 +1,368 @@
+use super::args::ExtArgs;
+use super::forward::{own_supertraits, Delegation};
+use super::process_impl::{idents, is_unsized, replace_self};
+use super::routing::item_cfgs;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    FnArg, Ident, ItemImpl, ItemTrait, ReturnType, Signature, Token, TraitItem, TraitItemFn, Type,
+};
+
+/// Whether a return value of this type can be produced again and again by a stored closure, which
+/// isn't the case if it borrows from the arguments or mentions generic parameters of the method
+fn storable(ty: &Type, sig: &Signature) -> bool {
+    let spelled = ty.to_token_stream().to_string();
+    let mentioned = idents(ty);
+    !spelled.contains('&')
+        && !spelled.contains('\'')
+        && !mentioned.contains("impl")
+        && !sig
+            .generics
+            .type_params()
+            .any(|p| mentioned.contains(&p.ident.to_string()))
+}
+
+/// The signature with all argument patterns replaced by `_`, since the arguments aren't used
+fn ignore_args(sig: &Signature) -> Signature {
+    let mut sig = sig.clone();
+    for arg in &mut sig.inputs {
+        if let FnArg::Typed(t) = arg {
+            *t.pat = syn::parse_quote!(_);
+        }
+    }
+    sig
+}
+
+fn unsupported(trait_def: &ItemTrait, what: &str) -> syn::Error {
+    syn::Error::new(
+        trait_def.ident.span(),
+        format!("`gen_mock` doesn't support traits with {}", what),
+    )
+}
+
+/// A struct that implements the trait with preset return values, for tests
+///
+/// Methods that return `()` do nothing by default, all other methods panic until a closure that
+/// produces the return value is set with `set_method`. That isn't possible for methods whose
+/// return values borrow or are generic, or that don't take `self`, so those always panic.
+pub fn gen_mock(args: &ExtArgs, trait_def: &ItemTrait, mock: &Ident) -> syn::Result<TokenStream2> {
+    if !trait_def.generics.params.is_empty() {
+        return Err(unsupported(trait_def, "generic parameters"));
+    }
+    // the stored closures can't be cloned
+    if args.dyn_clone {
+        return Err(unsupported(trait_def, "`dyn_clone`"));
+    }
+
+    let mut fields = Vec::new();
+    let mut setters = Vec::new();
+    let mut methods = Vec::new();
+    for ti in &trait_def.items {
+        let (m, cfg) = match ti {
+            TraitItem::Fn(TraitItemFn {
+                default: None,
+                sig,
+                attrs,
+                ..
+            }) => (sig, item_cfgs(attrs)),
+            TraitItem::Fn(_) => continue,
+            TraitItem::Const(_) => return Err(unsupported(trait_def, "associated constants")),
+            TraitItem::Type(_) => return Err(unsupported(trait_def, "associated types")),
+            _ => continue,
+        };
+        let sig = ignore_args(m);
+        let name = &m.ident;
+        let body = match &m.output {
+            ReturnType::Default => quote!({}),
+            ReturnType::Type(_, ty) if m.receiver().is_some() && storable(ty, m) => {
+                let mut ty = (**ty).clone();
+                replace_self(&mut ty, &syn::parse_quote!(#mock));
+                let setter = format_ident!("set_{}", name);
+                let message = format!("no return value of `{}::{}` was set", mock, name);
+                let doc = format!(" Make `{}` return what `f` returns", name);
+                fields.push(quote!(#(#cfg)* #name: Option<Box<dyn Fn() -> #ty>>));
+                setters.push(quote! {
+                    #(#cfg)*
+                    #[doc = #doc]
+                    pub fn #setter(&mut self, f: impl Fn() -> #ty + 'static) -> &mut Self {
+                        self.#name = Some(Box::new(f));
+                        self
+                    }
+                });
+                quote!({
+                    match &self.#name {
+                        Some(f) => f(),
+                        None => panic!(#message),
+                    }
+                })
+            }
+            ReturnType::Type(..) => {
+                let message = format!("`{}::{}` can't have a return value", mock, name);
+                quote!({ panic!(#message) })
+            }
+        };
+        methods.push(quote!(#(#cfg)* #sig #body));
+    }
+
+    let vis = args.vis();
+    let cfg = args.cfg_attrs();
+    let trait_path = args.trait_path(&trait_def.ident);
+    let supertraits = own_supertraits(args, trait_def);
+    let cfg_all = quote!(#(#cfg)*);
+    let doc = format!(" A mock implementation of [`{}`]", trait_def.ident);
+    Ok(quote! {
+        #[cfg(test)]
+        #(#cfg)*
+        #[doc = #doc]
+        #[derive(Default)]
+        #vis struct #mock {
+            #(#fields,)*
+        }
+
+        #[cfg(test)]
+        #(#cfg)*
+        #[allow(dead_code)]
+        impl #mock {
+            #(#setters)*
+        }
+
+        #(
+            #[cfg(test)]
+            #cfg_all
+            impl #supertraits for #mock {}
+        )*
+
+        #[cfg(test)]
+        #(#cfg)*
+        impl #trait_path for #mock {
+            #(#methods)*
+        }
+    })
+}
+
+/// A struct that wraps a value of the self type and implements the trait by delegating to it,
+/// while recording the calls (with the arguments that implement `Debug`), for tests
+pub fn gen_spy(
+    args: &ExtArgs,
+    trait_def: &ItemTrait,
+    item: &ItemImpl,
+    spy: &Ident,
+) -> syn::Result<TokenStream2> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "`gen_spy` doesn't support generic impls",
+        ));
+    }
+    let self_ty = &*item.self_ty;
+    let trait_path = args.trait_path(&trait_def.ident);
+    let helpers = format_ident!("__{}_args", spy);
+
+    let delegation = Delegation {
+        target: self_ty,
+        trait_path: &trait_path,
+        option: "gen_spy",
+        span: args.span(),
+    };
+    // there's nowhere to record calls of functions without `self`
+    let described = Ident::new("described", args.span());
+    let items = delegation.items(trait_def, quote!(inner), |method, args| {
+        let method = method.to_string();
+        quote! {
+            #[allow(unused_imports)]
+            use #helpers::{Described as _, Opaque as _};
+            let #described: Vec<String> = vec![#((&#helpers::Arg(&#args)).describe()),*];
+            self.calls
+                .borrow_mut()
+                .push(format!("{}({})", #method, #described.join(", ")));
+        }
+    })?;
+
+    let stored = if is_unsized(self_ty) {
+        quote!(Box<#self_ty>)
+    } else {
+        quote!(#self_ty)
+    };
+    let vis = args.vis();
+    let cfg = args.cfg_attrs();
+    let cfg_all = quote!(#(#cfg)*);
+    let supertraits = own_supertraits(args, trait_def);
+    // `DynClone` is implemented for all `Clone` types
+    let derive = if args.dyn_clone {
+        quote!(#[derive(Clone)])
+    } else {
+        TokenStream2::new()
+    };
+    let doc = format!(
+        " Wraps a value and implements [`{}`] for it, recording the calls",
+        trait_def.ident
+    );
+    Ok(quote! {
+        #[cfg(test)]
+        #(#cfg)*
+        #[doc = #doc]
+        #derive
+        #vis struct #spy {
+            pub inner: #stored,
+            calls: std::cell::RefCell<Vec<String>>,
+        }
+
+        #[cfg(test)]
+        #(#cfg)*
+        #[allow(dead_code)]
+        impl #spy {
+            pub fn new(inner: #stored) -> Self {
+                Self {
+                    inner,
+                    calls: Default::default(),
+                }
+            }
+
+            /// The calls so far, like `name("argument", _)`, where `_` stands for arguments
+            /// that don't implement `Debug`
+            pub fn calls(&self) -> Vec<String> {
+                self.calls.borrow().clone()
+            }
+        }
+
+        // autoref specialization: `Described` is used if the argument implements `Debug`,
+        // `Opaque` otherwise
+        #[cfg(test)]
+        #(#cfg)*
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        mod #helpers {
+            pub struct Arg<'a, T: ?Sized>(pub &'a T);
+
+            pub trait Described {
+                fn describe(&self) -> String;
+            }
+
+            impl<T: ?Sized + std::fmt::Debug> Described for Arg<'_, T> {
+                fn describe(&self) -> String {
+                    format!("{:?}", self.0)
+                }
+            }
+
+            pub trait Opaque {
+                fn describe(&self) -> String;
+            }
+
+            impl<T: ?Sized> Opaque for &Arg<'_, T> {
+                fn describe(&self) -> String {
+                    "_".to_string()
+                }
+            }
+        }
+
+        #(
+            #[cfg(test)]
+            #cfg_all
+            impl #supertraits for #spy {}
+        )*
+
+        #[cfg(test)]
+        #(#cfg)*
+        impl #trait_path for #spy {
+            #(#items)*
+        }
+    })
+}
+
+/// The argument of `dummy_impl(...)`: the types and, optionally, `bodies = default`
+#[derive(Clone, Default)]
+pub struct DummyImpls {
+    pub types: Vec<Type>,
+    /// Return `Default::default()` instead of panicking
+    pub default_bodies: bool,
+}
+
+impl Parse for DummyImpls {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut out = DummyImpls::default();
+        while !input.is_empty() {
+            if input.peek(syn::Ident) && input.peek2(Token![=]) {
+                let key: Ident = input.parse()?;
+                if key != "bodies" {
+                    return Err(syn::Error::new(key.span(), "expected `bodies = ...`"));
+                }
+                input.parse::<Token![=]>()?;
+                let bodies: Ident = input.parse()?;
+                out.default_bodies = match bodies.to_string().as_str() {
+                    "default" => true,
+                    "unimplemented" => false,
+                    _ => {
+                        return Err(syn::Error::new(
+                            bodies.span(),
+                            "expected `default` or `unimplemented`",
+                        ))
+                    }
+                };
+            } else {
+                out.types.push(input.parse()?);
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Impls of the trait for the given types that don't do anything
+pub fn dummy_impls(
+    args: &ExtArgs,
+    trait_def: &ItemTrait,
+    dummies: &DummyImpls,
+) -> syn::Result<TokenStream2> {
+    let mut methods = Vec::new();
+    for ti in &trait_def.items {
+        match ti {
+            TraitItem::Fn(m) => {
+                let sig = ignore_args(&m.sig);
+                let body = if dummies.default_bodies {
+                    quote!(Default::default())
+                } else {
+                    let message = format!("`{}` isn't implemented for this type", sig.ident);
+                    quote!(unimplemented!(#message))
+                };
+                let cfg = item_cfgs(&m.attrs);
+                methods.push(quote!(#(#cfg)* #sig { #body }));
+            }
+            TraitItem::Const(c) => {
+                return Err(syn::Error::new(
+                    c.ident.span(),
+                    "`dummy_impl` doesn't support associated constants",
+                ))
+            }
+            TraitItem::Type(t) => {
+                return Err(syn::Error::new(
+                    t.ident.span(),
+                    "`dummy_impl` doesn't support associated types",
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    let cfg = args.glue_attrs();
+    let trait_path = args.trait_path(&trait_def.ident);
+    let (impl_generics, ty_generics, where_clause) = trait_def.generics.split_for_impl();
+    let mut out = TokenStream2::new();
+    for ty in &dummies.types {
+        for supertrait in own_supertraits(args, trait_def) {
+            out.extend(quote! {
+                #(#cfg)*
+                impl #impl_generics #supertrait for #ty #where_clause {}
+            });
+        }
+        out.extend(quote! {
+            #(#cfg)*
+            impl #impl_generics #trait_path #ty_generics for #ty #where_clause {
+                #(#methods)*
+            }
+        });
+    }
+    Ok(out)
+}
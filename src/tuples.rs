@@ -0,0 +1,209 @@
+use proc_macro2::{Delimiter, Group, Ident, Literal, Span, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{ImplItem, ItemImpl, LitInt, Token, Type, TypeParamBound, WherePredicate};
+
+/// The placeholder for the tuple type (and, inside `for_tuples!`, for each element)
+const PLACEHOLDER: &str = "Tuple";
+
+/// The argument of `tuples(...)`: either `a..=b`, `a..b` or just `n` (meaning `1..=n`)
+pub struct Arities {
+    pub start: usize,
+    pub end_inclusive: usize,
+}
+
+impl Parse for Arities {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let first: LitInt = input.parse()?;
+        let (start, end_inclusive, last) = if input.is_empty() {
+            (1, Some(first.base10_parse()?), first)
+        } else if input.peek(Token![..=]) {
+            input.parse::<Token![..=]>()?;
+            let last: LitInt = input.parse()?;
+            (first.base10_parse()?, Some(last.base10_parse()?), last)
+        } else {
+            input.parse::<Token![..]>()?;
+            let last: LitInt = input.parse()?;
+            let end_inclusive = last.base10_parse::<usize>()?.checked_sub(1);
+            (first.base10_parse()?, end_inclusive, last)
+        };
+        match end_inclusive {
+            Some(end_inclusive) if start <= end_inclusive => Ok(Arities {
+                start,
+                end_inclusive,
+            }),
+            _ => Err(syn::Error::new(
+                last.span(),
+                "the range of arities is empty",
+            )),
+        }
+    }
+}
+
+pub fn is_placeholder(ty: &Type) -> bool {
+    matches!(ty, Type::Path(p) if p.qself.is_none() && p.path.is_ident(PLACEHOLDER))
+}
+
+/// Remove the `Tuple: ...` predicates, which are meant for every element, from the where clause
+pub fn take_element_bounds(item: &mut ItemImpl) -> Vec<TypeParamBound> {
+    let mut bounds = Vec::new();
+    if let Some(c) = &mut item.generics.where_clause {
+        let predicates = std::mem::take(&mut c.predicates);
+        for p in predicates {
+            match p {
+                WherePredicate::Type(t) if is_placeholder(&t.bounded_ty) => bounds.extend(t.bounds),
+                p => c.predicates.push(p),
+            }
+        }
+    }
+    bounds
+}
+
+/// Replace the placeholder in `ts`, expanding `for_tuples!( #( ... )sep* )` for each element
+fn substitute(ts: TokenStream2, elems: &[Ident]) -> TokenStream2 {
+    let tokens: Vec<TokenTree> = ts.into_iter().collect();
+    let mut out = TokenStream2::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            TokenTree::Ident(id) if id == "for_tuples" => {
+                if let (Some(TokenTree::Punct(p)), Some(TokenTree::Group(g))) =
+                    (tokens.get(i + 1), tokens.get(i + 2))
+                {
+                    if p.as_char() == '!' {
+                        out.extend(repeat(g.stream(), elems));
+                        i += 3;
+                        continue;
+                    }
+                }
+                out.extend(Some(tokens[i].clone()));
+            }
+            TokenTree::Ident(id) if id == PLACEHOLDER => {
+                out.extend(quote!(Self));
+            }
+            TokenTree::Group(g) => {
+                let mut new = Group::new(g.delimiter(), substitute(g.stream(), elems));
+                new.set_span(g.span());
+                out.extend(Some(TokenTree::Group(new)));
+            }
+            tt => out.extend(Some(tt.clone())),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Expand the inside of a `for_tuples!( #( ... )sep* )`
+fn repeat(ts: TokenStream2, elems: &[Ident]) -> TokenStream2 {
+    let tokens: Vec<TokenTree> = ts.into_iter().collect();
+    let mut out = TokenStream2::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match (&tokens[i], tokens.get(i + 1)) {
+            (TokenTree::Punct(p), Some(TokenTree::Group(g)))
+                if p.as_char() == '#' && g.delimiter() == Delimiter::Parenthesis =>
+            {
+                // everything up to the `*` is the separator
+                let mut sep = TokenStream2::new();
+                let mut j = i + 2;
+                while j < tokens.len() {
+                    match &tokens[j] {
+                        TokenTree::Punct(p) if p.as_char() == '*' => break,
+                        tt => sep.extend(Some(tt.clone())),
+                    }
+                    j += 1;
+                }
+                for (idx, elem) in elems.iter().enumerate() {
+                    if idx > 0 {
+                        out.extend(sep.clone());
+                    }
+                    out.extend(for_element(g.stream(), elem, idx));
+                }
+                i = j + 1;
+            }
+            (tt, _) => {
+                out.extend(Some(tt.clone()));
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Instantiate a repetition for one element: `Tuple` becomes the element type, `.Tuple` its index
+fn for_element(ts: TokenStream2, elem: &Ident, idx: usize) -> TokenStream2 {
+    let mut out = TokenStream2::new();
+    let mut after_dot = false;
+    for tt in ts {
+        let next_after_dot = matches!(&tt, TokenTree::Punct(p) if p.as_char() == '.');
+        match tt {
+            TokenTree::Ident(id) if id == PLACEHOLDER => {
+                if after_dot {
+                    out.extend(Some(TokenTree::Literal(Literal::usize_unsuffixed(idx))));
+                } else {
+                    out.extend(Some(TokenTree::Ident(elem.clone())));
+                }
+            }
+            TokenTree::Group(g) => {
+                let mut new = Group::new(g.delimiter(), for_element(g.stream(), elem, idx));
+                new.set_span(g.span());
+                out.extend(Some(TokenTree::Group(new)));
+            }
+            tt => out.extend(Some(tt)),
+        }
+        after_dot = next_after_dot;
+    }
+    out
+}
+
+/// Make the impl for the tuple with `arity` elements out of the impl for the placeholder
+pub fn instantiate(
+    item: &ItemImpl,
+    bounds: &[TypeParamBound],
+    arity: usize,
+) -> syn::Result<ItemImpl> {
+    let elems: Vec<Ident> = (0..arity)
+        .map(|i| format_ident!("{}{}", PLACEHOLDER, i, span = Span::call_site()))
+        .collect();
+
+    let mut item = item.clone();
+    for elem in &elems {
+        item.generics.params.push(syn::parse_quote!(#elem));
+        if !bounds.is_empty() {
+            let bounds: Punctuated<&TypeParamBound, Token![+]> = bounds.iter().collect();
+            item.generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(#elem: #bounds));
+        }
+    }
+    *item.self_ty = syn::parse_quote!((#(#elems,)*));
+
+    let items = std::mem::take(&mut item.items);
+    for ii in items {
+        let ts = substitute(ii.into_token_stream(), &elems);
+        item.items.push(syn::parse2::<ImplItem>(ts)?);
+    }
+
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Arities;
+
+    fn arities(s: &str) -> syn::Result<(usize, usize)> {
+        syn::parse_str::<Arities>(s).map(|a| (a.start, a.end_inclusive))
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(arities("3").unwrap(), (1, 3));
+        assert_eq!(arities("0..=2").unwrap(), (0, 2));
+        assert_eq!(arities("2..4").unwrap(), (2, 3));
+        for empty in ["0", "1..0", "2..2", "5..=2"] {
+            assert!(arities(empty).is_err(), "{}", empty);
+        }
+    }
+}
@@ -0,0 +1,6 @@
+fn main() {
+    // the flag of `coverage_off(coverage_nightly)`
+    println!("cargo:rustc-check-cfg=cfg(coverage_nightly)");
+    // the `cfg`-gated items of the `cfg_parity` test, which has to pass either way
+    println!("cargo:rustc-check-cfg=cfg(cfg_parity)");
+}
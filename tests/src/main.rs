@@ -2,9 +2,11 @@
 //!
 //! This way (instead of doctests) has the advantage of easier `cargo expand`ability
 
-use ext_trait::ext;
+use ext_trait::{ext, ext_alias, ext_block, ext_combine, ext_delegate, ext_mod, ext_trait_name};
 use std::marker::PhantomData;
 
+pub mod stress;
+
 #[ext]
 impl<T> T {
     fn foo() {}
@@ -39,4 +41,1599 @@ impl<T: A> T {
 // #[ext(pub A B)]
 // impl<T> T {}
 
+#[ext(pub, id = "u8-succ")]
+impl u8 {
+    fn succ(self) -> u8 {
+        self + 1
+    }
+}
+
+ext_trait_name!(pub U8Succ = "u8-succ");
+
+pub fn succ_of<T: U8Succ>(x: T) -> u8 {
+    x.succ()
+}
+
+#[ext(pub SealedCovered, sealed, coverage_off(coverage_nightly))]
+impl str {
+    fn covered(&self) {}
+}
+
+#[ext(pub ImplOnlyAttrs, impl_only(lints, inline))]
+impl str {
+    #[inline]
+    #[allow(clippy::needless_lifetimes)]
+    fn impl_only_attrs<'a>(&'a self) -> &'a str {
+        self
+    }
+}
+
+// conditionally applied `#[ext]` behaves exactly like the direct form, including the generated name
+#[cfg_attr(all(), ext(pub, id = "i8-conditional"))]
+impl i8 {
+    fn conditional(self) {}
+}
+
+ext_trait_name!(pub I8Conditional = "i8-conditional");
+
+pub fn conditional<T: I8Conditional>(x: T) {
+    x.conditional()
+}
+
+#[ext_mod(pub)]
+pub mod conditional_in_mod {
+    pub struct Local;
+
+    #[cfg_attr(all(), ext(LocalEnabled))]
+    impl Local {
+        pub fn enabled(&self) {}
+    }
+
+    // without the `cfg_attr`, this stays an inherent impl
+    #[cfg_attr(any(), ext(LocalDisabled))]
+    impl Local {
+        pub fn disabled(&self) {}
+    }
+
+    #[ext_trait::ext(LocalQualified)]
+    impl Local {
+        pub fn qualified(&self) {}
+    }
+
+    pub fn both(l: &Local) {
+        l.enabled();
+        l.disabled();
+        l.qualified();
+    }
+}
+
+/// Docs
+#[ext(pub SlimImpl, slim_impl)]
+impl str {
+    /// More docs
+    #[must_use]
+    fn slim(&self) -> usize {
+        0
+    }
+}
+
+// the same predicate from several sources only ends up in the where clause once
+#[ext(pub DedupedPredicates)]
+impl<T: Clone> T
+where
+    T: Clone,
+    Self: Clone,
+{
+    fn deduped(&self) -> Self {
+        self.clone()
+    }
+}
+
+#[ext(pub InlineAll, inline_all)]
+impl str {
+    fn inlined(&self) {}
+
+    #[inline(never)]
+    fn not_inlined(&self) {}
+}
+
+// identical blocks in different places define different traits
+#[ext]
+impl u16 {
+    fn twin(self) {}
+}
+
+#[ext]
+impl u16 {
+    fn twin(self) {}
+}
+
+macro_rules! twice {
+    () => {
+        #[ext]
+        impl u16 {
+            fn other_twin(self) {}
+        }
+    };
+}
+
+// blocks from the body of a `macro_rules!` macro are in the same place every time, but still
+// define different traits
+twice!();
+twice!();
+
+// for blanket impls, `{type}` is the first bound
+#[ext(pub, name = "{type}Ext")]
+impl<I> I
+where
+    I: Iterator + Sized,
+{
+    fn count_all(self) -> usize {
+        self.count()
+    }
+}
+
+pub fn count_all<I: IteratorExt<I>>(i: I) -> usize {
+    i.count_all()
+}
+
+#[ext(pub traits::BytesExt, sealed)]
+impl [u8] {
+    fn first_byte(&self) -> Option<u8> {
+        self.first().copied()
+    }
+}
+
+pub fn first_byte<T: traits::BytesExt + ?Sized>(b: &T) -> Option<u8> {
+    b.first_byte()
+}
+
+#[ext(pub HiddenModule, hidden_module)]
+impl str {
+    fn hidden(&self) {}
+}
+
+pub fn hidden<T: HiddenModule + ?Sized>(s: &T) {
+    s.hidden()
+}
+
+#[ext(pub Renamed)]
+impl str {
+    #[ext(rename = "try_split")]
+    fn split_once_ext(&self, c: char) -> Option<(&str, &str)> {
+        let i = self.find(c)?;
+        Some((&self[..i], &self[i + c.len_utf8()..]))
+    }
+}
+
+pub fn try_split<T: Renamed + ?Sized>(s: &T) -> Option<(&str, &str)> {
+    s.try_split(',')
+}
+
+// raw identifiers stay raw in both the trait and the impl
+#[ext(pub r#RawExt, sealed, hidden_module)]
+impl u8 {
+    #[allow(non_upper_case_globals)]
+    const r#type: u8 = 0;
+
+    fn r#async(&self) -> u8 {
+        Self::r#type
+    }
+
+    #[ext(group = "more")]
+    fn r#await(&self) {}
+
+    #[ext(rename = "r#match")]
+    fn matches(&self) {}
+}
+
+pub fn raw<T: r#RawExt + RawMoreExt>(x: T) -> u8 {
+    x.r#await();
+    x.r#match();
+    x.r#async() + T::r#type
+}
+
+#[ext(pub, name = "{type}Ext", type_name(wrappers = last, generics = omit))]
+impl<'a> &'a Vec<u8> {
+    fn first_or_zero(self) -> u8 {
+        self.first().copied().unwrap_or(0)
+    }
+}
+
+pub fn first_or_zero<'a, T: VecRefExt<'a>>(v: T) -> u8 {
+    v.first_or_zero()
+}
+
+// `Vec<Nested>: Debug` also ends up in the where clause as `Vec<Self>: Debug`
+#[derive(Debug)]
+pub struct Nested;
+
+#[ext(pub NestedSelf)]
+impl Nested
+where
+    Self: Sized,
+    Vec<Nested>: std::fmt::Debug,
+{
+    fn debug_many(self) -> String {
+        format!("{:?}", vec![self])
+    }
+}
+
+pub fn debug_many<T: NestedSelf>(x: T) -> String
+where
+    Vec<T>: std::fmt::Debug,
+{
+    x.debug_many()
+}
+
+pub struct Wrapper<T>(T);
+
+// gets the twin `T: PartialEq<Wrapper<T>>`
+#[ext(pub BoundArgs)]
+impl<T> Wrapper<T>
+where
+    T: PartialEq<Self>,
+{
+    fn matches_inner(&self, other: &T) -> bool {
+        other == self
+    }
+}
+
+// the trait only says `Self: Clone`, the impl only `Vec<u8>: Clone`
+#[ext(pub SplitSelf, split_self_predicates)]
+impl Vec<u8>
+where
+    Vec<u8>: Clone,
+{
+    fn cloned_twice(&self) -> (Self, Self) {
+        (self.clone(), self.clone())
+    }
+}
+
+// gets `where Self: Sized` on `some` (but not on `by_ref`)
+#[ext(pub AutoSized)]
+impl<T> T {
+    fn some(self) -> Option<Self> {
+        Some(self)
+    }
+
+    fn by_ref(&self) -> &Self {
+        self
+    }
+}
+
+// `[T]` isn't `Sized`, so nothing is added
+#[ext(pub SliceRefs)]
+impl<T> [T] {
+    fn first_and_rest(&self) -> Option<(&T, &Self)> {
+        self.split_first()
+    }
+}
+
+// outlives predicates are mirrored like any other (`Vec<&'a u8>: 'a` and `Self: 'static`)
+#[ext(pub Outlives)]
+impl<'a> Vec<&'a u8>
+where
+    Self: 'a,
+    Vec<&'a u8>: 'static,
+{
+    fn outlives(&self) -> usize {
+        self.len()
+    }
+}
+
+// the trait itself has no bounds, only `duplicated` requires `T: Clone`
+#[ext(pub PerMethodBounds, per_method_bounds)]
+impl<T: Clone> Vec<T> {
+    fn duplicated(&self) -> Self {
+        let mut v = self.clone();
+        v.extend_from_slice(self);
+        v
+    }
+
+    fn count(&self, _: u8) -> usize {
+        0
+    }
+}
+
+pub struct NotClone;
+
+impl PerMethodBounds<NotClone> for Vec<NotClone> {
+    fn count(&self, _: u8) -> usize {
+        self.len()
+    }
+
+    // can never be called, since `NotClone: Clone` doesn't hold
+    fn duplicated(&self) -> Self {
+        unreachable!()
+    }
+}
+
+// the default `first_cloned` isn't in the impl anymore, but still gets `T: Clone`
+#[ext(pub DefaultedBounds, per_method_bounds)]
+impl<T: Clone> Vec<T> {
+    #[ext(default)]
+    fn first_cloned(&self) -> Option<T> {
+        self.first_ref().cloned()
+    }
+
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn first_ref(&self) -> Option<&T> {
+        self.first()
+    }
+}
+
+#[test]
+fn per_method_bounds_with_defaults() {
+    assert_eq!(vec![1, 2].first_cloned(), Some(1));
+    assert_eq!(DefaultedBounds::size(&vec![1, 2]), 2);
+}
+
+// qualified paths survive (and get `Self` twins: `<Self as Iterator>::Item: Clone`)
+#[ext(pub QualifiedPredicates)]
+impl<I: Iterator> I
+where
+    <I as Iterator>::Item: Clone,
+{
+    fn first_twice(&mut self) -> Option<(<Self as Iterator>::Item, I::Item)> {
+        let first = self.next()?;
+        Some((first.clone(), first))
+    }
+}
+
+#[ext(pub WithDefault, defaults(T = u8))]
+impl<T: Copy> [T] {
+    fn first_copied(&self) -> Option<T> {
+        self.first().copied()
+    }
+}
+
+pub fn first_copied<S: WithDefault + ?Sized>(s: &S) -> Option<u8> {
+    s.first_copied()
+}
+
+/// Docs with a generated usage section
+#[ext(pub UsageDocs, usage = crate)]
+impl str {
+    fn repeat_with(&self, sep: &str, n: usize) -> String {
+        vec![self; n].join(sep)
+    }
+}
+
+#[deny(missing_docs)]
+pub mod documented {
+    //! A module that requires docs
+
+    use ext_trait::ext;
+
+    #[ext(pub docs::Undocumented, allow_missing_docs)]
+    impl str {
+        fn undocumented(&self) {}
+    }
+}
+
+pub struct Countdown(u8);
+
+#[ext(pub NotableCountdown, notable)]
+impl Countdown {
+    fn tick(&mut self) -> bool {
+        self.0 = self.0.saturating_sub(1);
+        self.0 == 0
+    }
+}
+
+/// Docs that get a link to `Vec<u8>` appended
+#[ext(pub linked::LinkedDocs, doc_links(see_also))]
+impl Vec<u8> {
+    /// Gets a link back to `linked::LinkedDocs`
+    fn linked(&self) {}
+
+    fn undocumented(&self) {}
+}
+
+#[ext(pub PartlyHidden)]
+impl str {
+    fn shown(&self) -> usize {
+        self.helper()
+    }
+
+    #[ext(hide)]
+    fn helper(&self) -> usize {
+        self.len()
+    }
+}
+
+// `old_way` is only deprecated in the trait, where it has an effect
+#[ext(pub Deprecations)]
+impl str {
+    #[deprecated = "use `new_way`"]
+    fn old_way(&self) {}
+
+    fn new_way(&self) {}
+}
+
+#[ext(pub DeprecatedTrait, deprecated = "use `Deprecations`", hidden_module, sealed, alias = pub OldAlias)]
+impl str {
+    fn older_way(&self) {}
+}
+
+/// Only on the impl
+#[ext(pub DocsOnImpl, docs_on_impl)]
+impl str {
+    /// Also only on the impl
+    #[doc(alias = "still_on_the_trait")]
+    fn documented_in_source(&self) {}
+}
+
+/// The usage section is a doctest (which is not run for binaries like this one)
+#[ext(pub DoctestExample, usage = tests, doctest_example = vec![1, 2])]
+impl Vec<u8> {
+    fn sum(&self, start: u8) -> u8 {
+        self.iter().fold(start, |a, b| a + b)
+    }
+
+    fn total(&self) -> u8 {
+        self.sum(0)
+    }
+}
+
+// the trait has `LIMIT`, `Unit`, `above` and `below`, in that order
+#[ext(pub Sorted, sorted)]
+impl u32 {
+    fn below(&self) -> bool {
+        *self < Self::LIMIT
+    }
+
+    type Unit = ();
+
+    fn above(&self) -> bool {
+        !self.below()
+    }
+
+    const LIMIT: u32 = 10;
+}
+
+pub struct Connection;
+
+#[ext(pub connection::ConnectionExt, gen_mock = MockConnection, sealed)]
+impl Connection {
+    fn query(&self, _sql: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn close(&mut self) {}
+
+    fn name(&self) -> &str {
+        "connection"
+    }
+}
+
+#[test]
+fn gen_mock() {
+    use connection::ConnectionExt;
+
+    let mut mock = MockConnection::default();
+    mock.set_query(|| vec!["row".to_string()]);
+    assert_eq!(mock.query("select"), ["row"]);
+    mock.close();
+    let unset = std::panic::catch_unwind(|| MockConnection::default().query(""));
+    assert!(unset.is_err());
+}
+
+pub struct Db;
+
+// in test builds, mockall generates `MockDbExt`
+#[ext(pub DbExt, mock)]
+impl Db {
+    type Key = u32;
+
+    fn lookup(&self, _key: u32, _table: &str) -> Option<String> {
+        None
+    }
+}
+
+#[test]
+fn mockall_mock() {
+    fn describe(db: &impl DbExt<Key = u32>) -> String {
+        db.lookup(1, "users").unwrap_or_default()
+    }
+
+    let mut mock = MockDbExt::new();
+    mock.expect_lookup()
+        .withf(|&key, table| key == 1 && table == "users")
+        .return_const(Some("one".to_string()));
+    assert_eq!(describe(&mock), "one");
+    assert_eq!(describe(&Db), "");
+}
+
+#[cfg_attr(test, faux::create)]
+pub struct Client {
+    base: String,
+}
+
+#[cfg_attr(test, faux::methods)]
+impl Client {
+    pub fn new(base: &str) -> Self {
+        Client {
+            base: base.to_string(),
+        }
+    }
+}
+
+#[ext(pub ClientExt, faux)]
+impl Client {
+    fn fetch(&self, id: u32) -> String {
+        format!("{}/{}", self.base, id)
+    }
+}
+
+#[test]
+fn faux_double() {
+    assert_eq!(Client::new("api").fetch(1), "api/1");
+
+    let mut client = Client::faux();
+    faux::when!(client.fetch).then_return("faked".to_string());
+    assert_eq!(client.fetch(1), "faked");
+}
+
+pub struct Opaque;
+
+#[ext(pub Counter, gen_spy = CounterSpy)]
+impl Vec<u32> {
+    const START: u32 = 0;
+
+    fn add(&mut self, n: u32) {
+        self.push(n);
+    }
+
+    fn add_opaque(&mut self, _: Opaque) {
+        self.push(0);
+    }
+
+    fn total(&self) -> u32 {
+        self.iter().sum()
+    }
+}
+
+#[test]
+fn gen_spy() {
+    let mut spy = CounterSpy::new(vec![]);
+    spy.add(2);
+    spy.add_opaque(Opaque);
+    assert_eq!(spy.total(), 2);
+    assert_eq!(spy.calls(), ["add(2)", "add_opaque(_)", "total()"]);
+    assert_eq!(CounterSpy::START, 0);
+}
+
+pub struct FileBackend;
+pub struct NullBackend;
+pub struct EmptyBackend;
+
+#[ext(pub Backend, dummy_impl(NullBackend), sealed)]
+impl FileBackend {
+    fn store(&mut self, _data: &[u8]) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[ext(pub DefaultBackend, dummy_impl(EmptyBackend, bodies = default))]
+impl FileBackend {
+    fn load(&self) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+pub fn load_nothing() -> Vec<u8> {
+    EmptyBackend.load()
+}
+
+pub fn store_nowhere() -> fn(&mut NullBackend, &[u8]) -> std::io::Result<()> {
+    NullBackend::store
+}
+
+#[ext(pub Asserted, also_for(String), assert_impl_for(str, String))]
+impl str {
+    fn asserted(&self) {}
+}
+
+#[ext(pub BlanketAsserted, assert_impl_for(String, Box<[u8]>, &'static str))]
+impl<'a, T: std::fmt::Debug + 'a> T {
+    fn debugged(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[ext(PubIfTest, pub_if(test))]
+impl str {
+    fn pub_in_tests(&self) {}
+}
+
+#[ext(pub_if_any::PubIfAny, pub_if(any()), hidden_module)]
+impl str {
+    fn pub_crate(&self) {}
+}
+
+#[ext_mod(pub_if(test))]
+pub mod pub_if_in_mod {
+    #[ext(PubIfInMod)]
+    impl str {
+        fn pub_in_mod_tests(&self) {}
+    }
+}
+
+// `PubIfTest` and `PubIfInMod` are only `pub` in tests
+#[cfg(test)]
+pub fn pub_if<T: PubIfTest + pub_if_in_mod::PubIfInMod + pub_if_any::PubIfAny + ?Sized>(s: &T) {
+    s.pub_in_tests();
+    s.pub_in_mod_tests();
+    s.pub_crate();
+}
+
+#[ext(pub BufExt, split_mut)]
+impl Vec<u8> {
+    fn last_byte(&self) -> Option<u8> {
+        self.last().copied()
+    }
+
+    fn push_twice(&mut self, b: u8) {
+        self.push(b);
+        self.push(b);
+    }
+}
+
+pub fn last_byte<T: BufExt>(buf: &T) -> Option<u8> {
+    buf.last_byte()
+}
+
+pub fn push_twice<T: BufExtMut>(buf: &mut T) {
+    buf.push_twice(0)
+}
+
+pub struct Lines(Vec<String>);
+
+// `LinesExt` stays object safe, `IntoLinesExt` has the consuming `joined`
+#[ext(pub LinesExt, split_consuming)]
+impl Lines {
+    fn count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn joined(self) -> String {
+        self.0.join("\n")
+    }
+}
+
+pub fn object_safe(lines: &dyn LinesExt) -> usize {
+    lines.count()
+}
+
+pub fn joined<T: IntoLinesExt>(lines: T) -> String {
+    lines.joined()
+}
+
+pub struct Meters(pub f64);
+
+#[ext(pub MetersExt, also_inherent, also_for(f64))]
+impl Meters {
+    const ZERO: f64 = 0.0;
+
+    pub fn describe(&self, precision: usize, unit: &str) -> String {
+        format!("{:.*}{}", precision, 1.5, unit)
+    }
+
+    pub fn convert<T: From<u8>>(&self) -> T {
+        T::from(0)
+    }
+}
+
+// no import of `MetersExt` is needed for the inherent methods
+pub mod inherent_user {
+    pub fn describe(m: &super::Meters) -> String {
+        m.describe(2, "m")
+    }
+}
+
+pub fn generic_meters<T: MetersExt>(m: &T) -> u32 {
+    m.convert::<u32>() + T::ZERO as u32
+}
+
+#[ext(pub BytesSummary, newtype = RichBytes)]
+impl Vec<u8> {
+    fn checksum(&self) -> u8 {
+        self.iter().fold(0, |a, b| a.wrapping_add(*b))
+    }
+
+    fn into_inner(self) -> Vec<u8> {
+        self
+    }
+}
+
+#[ext(pub StrSummary, newtype = RichStr, sealed)]
+impl str {
+    fn words(&self) -> usize {
+        self.split_whitespace().count()
+    }
+}
+
+pub fn newtypes() -> (u8, usize, usize) {
+    let bytes = RichBytes::from(vec![1, 2]);
+    let words = RichStr(Box::from("two words"));
+    (bytes.checksum(), bytes.len(), words.words())
+}
+
+ext_combine!(
+    /// All string extensions at once
+    pub trait AllStrExt: Renamed + HiddenModule + PartlyHidden;
+);
+
+ext_combine!(pub trait AllIterExt<I>: IteratorExt<I> + Clone where I: Iterator + Sized);
+
+pub fn combined_user<S: AllStrExt + ?Sized>(s: &S) -> usize {
+    s.hidden();
+    s.try_split(',').map_or(0, |(a, _)| a.len()) + s.shown()
+}
+
+pub fn combined_str(s: &str) -> usize {
+    combined_user(s)
+}
+
+ext_alias!(pub BytesIter = Iterator<Item = u8> + Send);
+
+ext_alias!(
+    /// Collections that can be shared between threads
+    pub(crate) SharedIndex<I, V> = std::ops::Index<I, Output = V> + Sync where I: Copy;
+);
+
+pub fn checksum(bytes: impl BytesIter) -> u8 {
+    bytes.fold(0, u8::wrapping_add)
+}
+
+pub(crate) fn lookup<M: SharedIndex<usize, String>>(map: &M) -> &str {
+    &map[0]
+}
+
+pub fn aliases() -> usize {
+    let map = vec![String::new()];
+    checksum(vec![1, 2].into_iter()) as usize + lookup(&map).len()
+}
+
+pub struct Grid<const N: usize>([u8; N]);
+
+#[ext(pub, name = "{type}Ext")]
+impl<const N: usize> Grid<N> {
+    pub fn size(&self) -> usize {
+        N
+    }
+}
+
+#[ext(pub, name = "{type}BlockExt")]
+impl<const N: usize> Grid<{ N }> {
+    pub fn cells(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+#[ext(pub, name = "{type}Ext")]
+impl<const N: usize> [Grid<N>; N] {
+    pub fn diagonal(&self) -> [u8; N] {
+        let mut out = [0; N];
+        for (i, grid) in self.iter().enumerate() {
+            out[i] = grid.0[i];
+        }
+        out
+    }
+}
+
+pub fn grids() -> usize {
+    let grid = Grid([1, 2]);
+    let diagonal = <[Grid<2>; 2] as ArrayGridExt<2>>::diagonal(&[Grid([1, 2]), Grid([3, 4])]);
+    GridExt::size(&grid) + GridBlockExt::cells(&grid).len() + diagonal.len()
+}
+
+// the namespaced forms of the item options don't clash with other macros' helper attributes
+#[ext(pub Namespaced)]
+impl [u8] {
+    #[ext_trait::rename = "total"]
+    fn sum_bytes(&self) -> u32 {
+        self.iter().map(|&b| u32::from(b)).sum()
+    }
+
+    #[ext_trait::hide]
+    #[ext_trait::group = "extra"]
+    fn maximum(&self) -> Option<u8> {
+        self.iter().copied().max()
+    }
+
+    #[ext_trait::ext(rename = "minimum", group = "extra")]
+    fn min_byte(&self) -> Option<u8> {
+        self.iter().copied().min()
+    }
+}
+
+pub fn namespaced<T: Namespaced + NamespacedExtra + ?Sized>(bytes: &T) -> Option<u32> {
+    Some(bytes.total() + u32::from(bytes.maximum()?) + u32::from(bytes.minimum()?))
+}
+
+// `inline` and `cold` aren't allowed on trait methods without a body, so they stay on the impl
+#[ext(pub ImplAttrs)]
+impl u64 {
+    #[inline]
+    #[must_use]
+    fn halved(self) -> u64 {
+        self / 2
+    }
+
+    #[cold]
+    #[track_caller]
+    #[cfg_attr(test, inline(never))]
+    fn fail(self) -> ! {
+        panic!("{}", self)
+    }
+}
+
+pub fn impl_attrs(x: u64) -> u64 {
+    if x == 0 {
+        x.fail()
+    }
+    x.halved()
+}
+
+macro_rules! scaled {
+    ($name:ident, $trait_name:ident) => {
+        pub struct $name(pub f64);
+
+        #[ext(pub $trait_name, mixed_site, also_inherent, newtype = Wrapped)]
+        impl $name {
+            pub fn scaled(&self, factor: f64) -> f64 {
+                self.0 * factor
+            }
+        }
+    };
+}
+
+scaled!(Seconds, SecondsExt);
+
+pub fn seconds(s: Seconds) -> f64 {
+    SecondsExt::scaled(&s, 2.0) + Wrapped(s).scaled(3.0)
+}
+
+// a minimal stand-in for the `dyn-clone` crate, which the tests can't depend on
+mod dyn_clone {
+    pub trait DynClone {
+        fn __clone_box(&self) -> *mut ();
+    }
+
+    impl<T: Clone> DynClone for T {
+        fn __clone_box(&self) -> *mut () {
+            Box::into_raw(Box::new(self.clone())) as *mut ()
+        }
+    }
+
+    pub fn clone_box<T: ?Sized + DynClone>(t: &T) -> Box<T> {
+        let mut fat_ptr = t as *const T;
+        unsafe {
+            let data_ptr = &mut fat_ptr as *mut *const T as *mut *mut ();
+            *data_ptr = <T as DynClone>::__clone_box(t);
+            Box::from_raw(fat_ptr as *mut T)
+        }
+    }
+
+    macro_rules! clone_trait_object {
+        ($(<$($param:ident),*>)? $trait_path:path $(where $($pred:tt)*)?) => {
+            impl<$($($param),*)?> Clone for Box<dyn $trait_path> $(where $($pred)*)? {
+                fn clone(&self) -> Self {
+                    crate::dyn_clone::clone_box(&**self)
+                }
+            }
+        };
+    }
+    pub(crate) use clone_trait_object;
+}
+
+#[derive(Clone)]
+pub struct Circle(pub f64);
+
+#[ext(pub Shape, dyn_clone)]
+impl Circle {
+    fn area(&self) -> f64 {
+        3.0 * self.0 * self.0
+    }
+}
+
+#[ext(pub Labeled, dyn_clone)]
+impl<T: Clone + 'static> Vec<T> {
+    fn label(&self) -> usize {
+        self.len()
+    }
+}
+
+#[test]
+fn dyn_clone() {
+    let shape: Box<dyn Shape> = Box::new(Circle(1.0));
+    assert_eq!(shape.clone().area(), 3.0);
+    let labeled: Box<dyn Labeled<u8>> = Box::new(vec![1, 2]);
+    assert_eq!(labeled.clone().label(), 2);
+}
+
+// a minimal stand-in for the `downcast-rs` crate, which the tests can't depend on
+mod downcast_rs {
+    use std::any::Any;
+
+    pub trait Downcast: Any {
+        fn as_any(&self) -> &dyn Any;
+    }
+
+    impl<T: Any> Downcast for T {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    macro_rules! impl_downcast {
+        ($trait_:ident $(<$($param:ident),*>)? $(where $($pred:tt)*)?) => {
+            impl<$($($param),*)?> dyn $trait_<$($($param),*)?> $(where $($pred)*)? {
+                pub fn is<__T: $trait_<$($($param),*)?>>(&self) -> bool {
+                    crate::downcast_rs::Downcast::as_any(self).is::<__T>()
+                }
+
+                pub fn downcast_ref<__T: $trait_<$($($param),*)?>>(&self) -> Option<&__T> {
+                    crate::downcast_rs::Downcast::as_any(self).downcast_ref::<__T>()
+                }
+            }
+        };
+    }
+    pub(crate) use impl_downcast;
+}
+
+pub struct Audio;
+
+#[ext(pub plugins::Plugin, downcast)]
+impl Audio {
+    fn name(&self) -> &'static str {
+        "audio"
+    }
+}
+
+#[ext(pub Handler, downcast)]
+impl<T: Clone + 'static> Vec<T> {
+    fn handle(&self, value: T) -> usize {
+        self.len() + vec![value].len()
+    }
+}
+
+#[test]
+fn downcast() {
+    let plugin: Box<dyn plugins::Plugin> = Box::new(Audio);
+    assert_eq!(plugin.name(), "audio");
+    assert!(plugin.is::<Audio>());
+    let handler: Box<dyn Handler<u8>> = Box::new(vec![1u8]);
+    assert_eq!(handler.handle(2), 2);
+    assert_eq!(handler.downcast_ref::<Vec<u8>>(), Some(&vec![1]));
+}
+
+#[derive(Clone)]
+pub struct Square(pub f64);
+
+#[derive(Clone)]
+pub struct NoPolygon;
+
+// the generated types get `DynClone` and `Downcast` from their blanket impls
+#[ext(
+    pub Polygon,
+    dyn_clone,
+    downcast,
+    newtype = SquareWrapper,
+    gen_spy = PolygonSpy,
+    dummy_impl(NoPolygon)
+)]
+impl Square {
+    fn sides(&self) -> u8 {
+        4
+    }
+}
+
+#[test]
+fn generated_types_with_blanket_supertraits() {
+    let wrapped: Box<dyn Polygon> = Box::new(SquareWrapper(Square(1.0)));
+    assert_eq!(wrapped.clone().sides(), 4);
+    assert!(wrapped.is::<SquareWrapper>());
+    let spy: Box<dyn Polygon> = Box::new(PolygonSpy::new(Square(2.0)));
+    assert_eq!(spy.clone().sides(), 4);
+    let dummy: Box<dyn Polygon> = Box::new(NoPolygon);
+    assert!(dummy.is::<NoPolygon>());
+}
+
+pub struct Temperature(pub f64);
+
+#[ext(pub TemperatureExt, lift(Option, Result))]
+impl Temperature {
+    const FREEZING: f64 = 0.0;
+
+    fn celsius(&self) -> f64 {
+        self.0
+    }
+
+    fn warm_up(&mut self, by: f64) {
+        self.0 += by;
+    }
+
+    fn describe(self, unit: &str) -> String {
+        format!("{}{}", self.0, unit)
+    }
+
+    fn unit() -> &'static str {
+        "C"
+    }
+}
+
+#[ext(pub Lengths, lift(Option))]
+impl<T> Vec<T>
+where
+    T: Clone,
+{
+    fn total_len(&self) -> usize {
+        self.len()
+    }
+}
+
+#[test]
+fn lift() {
+    let mut some = Some(Temperature(1.0));
+    some.warm_up(1.0);
+    assert_eq!(some.celsius(), 2.0);
+    assert_eq!(some.describe("C"), "2C");
+    let mut none: Option<Temperature> = None;
+    none.warm_up(1.0);
+    assert_eq!(none.celsius(), 0.0);
+    assert_eq!(none.describe("C"), "");
+    let err: Result<Temperature, ()> = Err(());
+    assert_eq!(err.celsius(), 0.0);
+    assert_eq!(<Option<Temperature> as TemperatureExt>::unit(), "C");
+    assert_eq!(<Result<Temperature, u8> as TemperatureExt>::FREEZING, 0.0);
+    assert_eq!(Some(vec![1, 2]).total_len(), 2);
+}
+
+#[ext(pub FreeBytes, also_fns)]
+impl [u8] {
+    fn byte_sum(&self) -> u8 {
+        self.iter().fold(0, |a, b| a.wrapping_add(*b))
+    }
+
+    fn zero(&mut self) {
+        self.iter_mut().for_each(|b| *b = 0);
+    }
+}
+
+#[ext(pub FreeVec, also_fns = vec_fns, split_consuming)]
+impl<T: Clone> Vec<T> {
+    fn doubled(&self) -> Self {
+        let mut out = self.clone();
+        out.extend_from_slice(self);
+        out
+    }
+
+    fn first_or<'a>(&'a self, default: &'a T) -> &'a T {
+        self.first().unwrap_or(default)
+    }
+
+    fn into_last(self) -> Option<T> {
+        self.last().cloned()
+    }
+
+    fn with_capacity_of<U>(_other: &[U]) -> Self {
+        Vec::new()
+    }
+}
+
+pub fn free_fns() -> usize {
+    let mut bytes = [1, 2];
+    let sum = byte_sum(&bytes);
+    zero(&mut bytes);
+    let v = vec_fns::doubled(&vec![1u8]);
+    let first = *vec_fns::first_or(&v, &0);
+    let last = vec_fns::into_last(v).unwrap_or(0);
+    let empty: Vec<u8> = vec_fns::with_capacity_of(&[()]);
+    usize::from(sum + first + last) + empty.len()
+}
+
+pub mod counters {
+    use ext_trait::ext;
+
+    #[ext(pub CounterExt, delegatable)]
+    impl Vec<u32> {
+        const START: u32 = 0;
+
+        fn count(&self) -> u32 {
+            self.iter().sum()
+        }
+
+        fn bump(&mut self, by: u32) {
+            self.push(by);
+        }
+
+        fn into_counts(self) -> Vec<u32> {
+            self
+        }
+
+        fn fresh() -> Vec<u32> {
+            Vec::new()
+        }
+    }
+}
+
+use counters::CounterExt;
+
+#[ext_delegate(counters::CounterExt, field = counts)]
+pub struct Tally {
+    pub label: &'static str,
+    pub counts: Vec<u32>,
+}
+
+#[ext_delegate(CounterExt)]
+pub struct Counted<T>(pub T);
+
+#[test]
+fn ext_delegate() {
+    let mut tally = Tally {
+        label: "tally",
+        counts: vec![1],
+    };
+    tally.bump(2);
+    assert_eq!(tally.count(), 3);
+    assert_eq!(tally.label, "tally");
+    assert_eq!(tally.into_counts(), vec![1, 2]);
+    assert_eq!(Tally::START, 0);
+    assert!(Tally::fresh().is_empty());
+
+    let counted = Counted(vec![4, 5]);
+    assert_eq!(counted.count(), 9);
+}
+
+#[ext(Reported, warn_dead_code)]
+impl u64 {
+    fn reported(&self) -> u64 {
+        *self
+    }
+}
+
+pub fn reported() -> u64 {
+    1.reported()
+}
+
+/// Documented extension methods
+#[ext(pub Documented, require_docs)]
+impl i8 {
+    /// Twice the largest value
+    const TWICE_MAX: i16 = 2 * i8::MAX as i16;
+
+    /// The absolute value as a `u8`
+    fn magnitude(&self) -> u8 {
+        self.unsigned_abs()
+    }
+}
+
+#[ext(Modern, msrv = "1.80")]
+impl Vec<u8> {
+    async fn first_async(&self) -> Option<u8> {
+        self.first().copied()
+    }
+
+    fn evens(&self) -> impl Iterator<Item = &u8> {
+        self.iter().filter(|b| *b % 2 == 0)
+    }
+}
+
+#[ext_mod(msrv = "1.60.0")]
+mod old_compilers {
+    #[ext(pub OldCompilerExt)]
+    impl Vec<u8> {
+        fn wide_total(&self) -> u32 {
+            self.iter().map(|&b| u32::from(b)).sum()
+        }
+    }
+}
+
+pub fn msrv() -> usize {
+    use old_compilers::OldCompilerExt;
+    let v = vec![1, 2];
+    drop(v.first_async());
+    v.evens().count() + v.wide_total() as usize
+}
+
+// the trait's `use<...>` bounds also capture `Self`
+#[ext(pub PreciseCaptures)]
+impl [u8] {
+    fn bytes_from<'a>(&'a self, skip: usize) -> impl Iterator<Item = u8> + use<'a> {
+        self.iter().copied().skip(skip)
+    }
+}
+
+pub fn precise_captures(bytes: &[u8]) -> usize {
+    bytes.bytes_from(1).count()
+}
+
+ext_block! {
+    /// Things that are text in some way
+    pub trait Stringish;
+
+    impl &str {
+        const OWNED: bool = false;
+
+        fn shout(&self) -> String {
+            self.to_uppercase()
+        }
+    }
+
+    impl String {
+        const OWNED: bool = true;
+
+        fn shout(&self) -> String {
+            let mut out = self.clone();
+            out.make_ascii_uppercase();
+            out
+        }
+    }
+}
+
+ext_block! {
+    #[ext(sealed)]
+    trait Sequence;
+
+    impl<T: Clone> Vec<T> {
+        fn last_item(&self) -> Option<T> {
+            self.last().cloned()
+        }
+    }
+
+    impl<U: Clone> std::collections::VecDeque<U> {
+        fn last_item(&self) -> Option<U> {
+            self.back().cloned()
+        }
+    }
+}
+
+#[test]
+fn ext_block() {
+    assert_eq!("abc".shout(), "ABC");
+    assert_eq!(String::from("abc").shout(), "ABC");
+    assert_eq!(
+        [<&str as Stringish>::OWNED, <String as Stringish>::OWNED],
+        [false, true]
+    );
+
+    let deque: std::collections::VecDeque<u8> = vec![1, 2].into();
+    assert_eq!(deque.last_item(), Some(2));
+    assert_eq!(vec![3].last_item(), Some(3));
+}
+
+mod inferred {
+    use ext_trait::ext;
+
+    #[ext(InferredPub, vis = infer)]
+    impl u8 {
+        pub const ONE: u8 = 1;
+
+        pub fn incremented(self) -> u8 {
+            self + Self::ONE
+        }
+    }
+
+    #[ext(NotInferred, vis = infer)]
+    impl u8 {
+        pub fn decremented(self) -> u8 {
+            self - 1
+        }
+
+        fn unchanged(self) -> u8 {
+            self
+        }
+    }
+
+    pub fn not_inferred() -> u8 {
+        2.decremented().unchanged()
+    }
+}
+
+pub fn inferred_vis() -> u8 {
+    use inferred::InferredPub;
+    1.incremented() + inferred::not_inferred()
+}
+
+#[ext(pub CowStr, forward(Cow))]
+impl str {
+    const SEPARATOR: char = ' ';
+
+    fn word_count(&self) -> usize {
+        self.split(Self::SEPARATOR).count()
+    }
+
+    fn starts_with_any(&self, prefixes: &[&str]) -> bool {
+        prefixes.iter().any(|p| self.starts_with(p))
+    }
+}
+
+#[ext(pub CowSlice, forward(Cow))]
+impl<T: Clone + PartialEq> [T] {
+    fn count_of(&self, item: &T) -> usize {
+        self.iter().filter(|i| *i == item).count()
+    }
+}
+
+#[test]
+fn forward_cow() {
+    use std::borrow::Cow;
+
+    let borrowed: Cow<str> = Cow::Borrowed("a b c");
+    let owned: Cow<str> = Cow::Owned("a b".to_string());
+    assert_eq!(borrowed.word_count(), 3);
+    assert_eq!(owned.word_count(), 2);
+    assert!(owned.starts_with_any(&["x", "a"]));
+    assert_eq!(<Cow<str> as CowStr>::SEPARATOR, ' ');
+
+    let slice: Cow<[u8]> = Cow::Owned(vec![1, 2, 1]);
+    assert_eq!(slice.count_of(&1), 2);
+}
+
+#[ext(
+    pub CfgParity,
+    newtype = CfgParityString,
+    also_fns = cfg_parity_fns,
+    lift(Option),
+    forward(Cow),
+    dummy_impl(u16),
+    gen_mock = CfgParityMock,
+    delegatable
+)]
+impl String {
+    fn always(&self) -> usize {
+        self.len()
+    }
+
+    #[cfg(cfg_parity)]
+    fn with_feature(&self) -> usize {
+        self.len() * 2
+    }
+
+    // the `inline` keeps this on the impl, but the `cfg` has to be on the trait as well
+    #[cfg_attr(not(cfg_parity), cfg(any()), inline)]
+    fn also_with_feature(&self) -> usize {
+        self.len() * 3
+    }
+}
+
+#[ext_delegate(CfgParity)]
+pub struct CfgParityDelegate(pub String);
+
+#[test]
+fn cfg_parity() {
+    let s = String::from("ab");
+    let wrapped = CfgParityString(s.clone());
+    let delegated = CfgParityDelegate(s.clone());
+    assert_eq!(s.always() + wrapped.always() + delegated.always(), 6);
+    assert_eq!(cfg_parity_fns::always(&s), 2);
+    assert_eq!(Some(s.clone()).always(), 2);
+    #[cfg(cfg_parity)]
+    {
+        assert_eq!(s.with_feature(), 4);
+        assert_eq!(s.also_with_feature(), 6);
+        assert_eq!(wrapped.with_feature() + delegated.also_with_feature(), 10);
+        assert_eq!(cfg_parity_fns::with_feature(&s), 4);
+        assert_eq!(std::borrow::Cow::Borrowed(&s).also_with_feature(), 6);
+    }
+}
+
+#[ext(name = "Bytes", self_in_defaults)]
+impl Vec<u8> {
+    #[ext(default)]
+    const FILLER: u8 = b' ';
+
+    fn empty() -> Self {
+        Vec::new()
+    }
+
+    fn push_byte(&mut self, b: u8) {
+        self.push(b);
+    }
+
+    #[ext(default)]
+    fn filled(n: usize) -> Self {
+        let mut out: Vec<u8> = Vec::<u8>::empty();
+        for _ in 0..n {
+            out.push_byte(<Vec<u8> as Bytes>::FILLER);
+        }
+        out
+    }
+}
+
+impl Bytes for String {
+    const FILLER: u8 = b'-';
+
+    fn empty() -> Self {
+        String::new()
+    }
+
+    fn push_byte(&mut self, b: u8) {
+        self.push(b as char);
+    }
+}
+
+#[test]
+fn defaults() {
+    assert_eq!(Vec::<u8>::filled(2), b"  ");
+    assert_eq!(String::filled(3), "---");
+}
+
+#[ext(name = "Width", also_for(u16, u32))]
+impl u8 {
+    #[ext(for(u16) { const BITS: u32 = 16; })]
+    #[ext(for(u32) { const BITS: u32 = 32; })]
+    const BITS: u32 = 8;
+
+    // override items are written like the ones of the impl, including the `pub`
+    #[ext(for(u32) {
+        pub fn describe(&self) -> String {
+            format!("{} (wide)", self)
+        }
+    })]
+    fn describe(&self) -> String {
+        format!("{} in {} bits", self, <Self as Width>::BITS)
+    }
+}
+
+#[test]
+fn overrides() {
+    assert_eq!(1u8.describe(), "1 in 8 bits");
+    assert_eq!(2u16.describe(), "2 in 16 bits");
+    assert_eq!(3u32.describe(), "3 (wide)");
+}
+
+#[ext(name = "Stamped", also_for(u16, i64))]
+impl u8 {
+    type Bytes = [u8; std::mem::size_of::<__Target>()];
+
+    fn le_bytes(self) -> Self::Bytes {
+        __Target::to_le_bytes(self)
+    }
+
+    fn name() -> &'static str {
+        std::any::type_name::<__Target>()
+    }
+
+    fn describe_max() -> String {
+        format!("{}", __Target::MAX)
+    }
+}
+
+#[test]
+fn target_placeholder() {
+    assert_eq!(1u16.le_bytes(), [1, 0]);
+    assert_eq!(<i64 as Stamped>::name(), "i64");
+    assert_eq!(u8::describe_max(), "255");
+}
+
+#[deny(clippy::needless_lifetimes, unused_lifetimes)]
+pub mod lints {
+    use ext_trait::ext;
+
+    #[ext(
+        pub,
+        name = "Quiet",
+        allow(clippy::needless_lifetimes, unused_lifetimes)
+    )]
+    impl str {
+        pub fn first_word<'a>(&'a self) -> &'a str {
+            self.split_whitespace().next().unwrap_or("")
+        }
+
+        pub fn unused<'b>(&self) -> usize {
+            self.len()
+        }
+    }
+}
+
+#[test]
+fn allowed_lints() {
+    use lints::Quiet;
+    assert_eq!(("a b".first_word(), "ab".unused()), ("a", 2));
+}
+
+#[ext(Contracted)]
+impl Vec<u8> {
+    /// Pushes into the spare capacity
+    #[ext(trait_doc = " Must not allocate")]
+    fn push_within(&mut self, b: u8) -> bool {
+        if self.len() < self.capacity() {
+            self.push(b);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[test]
+fn trait_docs() {
+    let mut v = Vec::with_capacity(1);
+    assert_eq!((v.push_within(1), v.push_within(2)), (true, false));
+}
+
+macro_rules! len_ext {
+    ($t:ty, $default:block) => {
+        #[ext(name = "MacroLen")]
+        impl $t
+        where
+            $t: std::fmt::Debug,
+        {
+            fn len_or(&self, fallback: Option<Box<Self>>) -> usize {
+                match fallback {
+                    Some(_) => $default,
+                    None => self.len(),
+                }
+            }
+        }
+    };
+}
+
+len_ext!(str, { 0 });
+
+#[test]
+fn macro_metavariables() {
+    assert_eq!("abc".len_or(None), 3);
+    assert_eq!("ab".len_or(Some("c".into())), 0);
+}
+
+pub mod adapters {
+    use ext_trait::ext;
+
+    #[ext(pub, name = "Chunky")]
+    impl<It: Iterator> It {
+        #[ext(adapter = SumChunks)]
+        pub fn sum_chunks(self, size: usize) -> SumChunks<Self> {
+            SumChunks { iter: self, size }
+        }
+
+        #[ext(adapter = MapPairs)]
+        pub fn map_pairs<F, B>(self, f: F) -> MapPairs<Self, F>
+        where
+            F: FnMut(Self::Item, Self::Item) -> B,
+        {
+            MapPairs { iter: self, f }
+        }
+    }
+
+    impl<I: Iterator<Item = u32>> Iterator for SumChunks<I> {
+        type Item = u32;
+
+        fn next(&mut self) -> Option<u32> {
+            let mut sum = None;
+            for x in self.iter.by_ref().take(self.size) {
+                *sum.get_or_insert(0) += x;
+            }
+            sum
+        }
+    }
+
+    impl<I: Iterator, F: FnMut(I::Item, I::Item) -> B, B> Iterator for MapPairs<I, F> {
+        type Item = B;
+
+        fn next(&mut self) -> Option<B> {
+            let a = self.iter.next()?;
+            let b = self.iter.next()?;
+            Some((self.f)(a, b))
+        }
+    }
+}
+
+#[test]
+fn iterator_adapters() {
+    use adapters::Chunky;
+    let sums: Vec<u32> = vec![1, 2, 3, 4, 5].into_iter().sum_chunks(2).collect();
+    assert_eq!(sums, [3, 7, 5]);
+    let products: Vec<u32> = (1..=5).map_pairs(|a, b| a * b).collect();
+    assert_eq!(products, [2, 12]);
+}
+
 fn main() {}
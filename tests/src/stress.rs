@@ -0,0 +1,330 @@
+//! A very large extension block, like the ones generated by other macros
+//!
+//! This only checks that such a block compiles; how long the expansion takes is measured by the
+//! `large_impls` benchmark of `ext_trait` itself.
+
+use ext_trait::ext;
+
+macro_rules! big_impl {
+    ($($method:ident $constant:ident),*) => {
+        #[ext(pub Big, also_for(u16, u32, u64), sealed, impl_only(inline))]
+        impl u8
+        where
+            u8: Copy,
+            Self: Clone,
+        {
+            $(
+                /// Docs that have to be copied
+                #[inline]
+                fn $method(&self) -> usize {
+                    Self::$constant
+                }
+
+                const $constant: usize = 0;
+            )*
+        }
+    };
+}
+
+big_impl!(
+    m000 C000,
+    m001 C001,
+    m002 C002,
+    m003 C003,
+    m004 C004,
+    m005 C005,
+    m006 C006,
+    m007 C007,
+    m008 C008,
+    m009 C009,
+    m010 C010,
+    m011 C011,
+    m012 C012,
+    m013 C013,
+    m014 C014,
+    m015 C015,
+    m016 C016,
+    m017 C017,
+    m018 C018,
+    m019 C019,
+    m020 C020,
+    m021 C021,
+    m022 C022,
+    m023 C023,
+    m024 C024,
+    m025 C025,
+    m026 C026,
+    m027 C027,
+    m028 C028,
+    m029 C029,
+    m030 C030,
+    m031 C031,
+    m032 C032,
+    m033 C033,
+    m034 C034,
+    m035 C035,
+    m036 C036,
+    m037 C037,
+    m038 C038,
+    m039 C039,
+    m040 C040,
+    m041 C041,
+    m042 C042,
+    m043 C043,
+    m044 C044,
+    m045 C045,
+    m046 C046,
+    m047 C047,
+    m048 C048,
+    m049 C049,
+    m050 C050,
+    m051 C051,
+    m052 C052,
+    m053 C053,
+    m054 C054,
+    m055 C055,
+    m056 C056,
+    m057 C057,
+    m058 C058,
+    m059 C059,
+    m060 C060,
+    m061 C061,
+    m062 C062,
+    m063 C063,
+    m064 C064,
+    m065 C065,
+    m066 C066,
+    m067 C067,
+    m068 C068,
+    m069 C069,
+    m070 C070,
+    m071 C071,
+    m072 C072,
+    m073 C073,
+    m074 C074,
+    m075 C075,
+    m076 C076,
+    m077 C077,
+    m078 C078,
+    m079 C079,
+    m080 C080,
+    m081 C081,
+    m082 C082,
+    m083 C083,
+    m084 C084,
+    m085 C085,
+    m086 C086,
+    m087 C087,
+    m088 C088,
+    m089 C089,
+    m090 C090,
+    m091 C091,
+    m092 C092,
+    m093 C093,
+    m094 C094,
+    m095 C095,
+    m096 C096,
+    m097 C097,
+    m098 C098,
+    m099 C099,
+    m100 C100,
+    m101 C101,
+    m102 C102,
+    m103 C103,
+    m104 C104,
+    m105 C105,
+    m106 C106,
+    m107 C107,
+    m108 C108,
+    m109 C109,
+    m110 C110,
+    m111 C111,
+    m112 C112,
+    m113 C113,
+    m114 C114,
+    m115 C115,
+    m116 C116,
+    m117 C117,
+    m118 C118,
+    m119 C119,
+    m120 C120,
+    m121 C121,
+    m122 C122,
+    m123 C123,
+    m124 C124,
+    m125 C125,
+    m126 C126,
+    m127 C127,
+    m128 C128,
+    m129 C129,
+    m130 C130,
+    m131 C131,
+    m132 C132,
+    m133 C133,
+    m134 C134,
+    m135 C135,
+    m136 C136,
+    m137 C137,
+    m138 C138,
+    m139 C139,
+    m140 C140,
+    m141 C141,
+    m142 C142,
+    m143 C143,
+    m144 C144,
+    m145 C145,
+    m146 C146,
+    m147 C147,
+    m148 C148,
+    m149 C149,
+    m150 C150,
+    m151 C151,
+    m152 C152,
+    m153 C153,
+    m154 C154,
+    m155 C155,
+    m156 C156,
+    m157 C157,
+    m158 C158,
+    m159 C159,
+    m160 C160,
+    m161 C161,
+    m162 C162,
+    m163 C163,
+    m164 C164,
+    m165 C165,
+    m166 C166,
+    m167 C167,
+    m168 C168,
+    m169 C169,
+    m170 C170,
+    m171 C171,
+    m172 C172,
+    m173 C173,
+    m174 C174,
+    m175 C175,
+    m176 C176,
+    m177 C177,
+    m178 C178,
+    m179 C179,
+    m180 C180,
+    m181 C181,
+    m182 C182,
+    m183 C183,
+    m184 C184,
+    m185 C185,
+    m186 C186,
+    m187 C187,
+    m188 C188,
+    m189 C189,
+    m190 C190,
+    m191 C191,
+    m192 C192,
+    m193 C193,
+    m194 C194,
+    m195 C195,
+    m196 C196,
+    m197 C197,
+    m198 C198,
+    m199 C199,
+    m200 C200,
+    m201 C201,
+    m202 C202,
+    m203 C203,
+    m204 C204,
+    m205 C205,
+    m206 C206,
+    m207 C207,
+    m208 C208,
+    m209 C209,
+    m210 C210,
+    m211 C211,
+    m212 C212,
+    m213 C213,
+    m214 C214,
+    m215 C215,
+    m216 C216,
+    m217 C217,
+    m218 C218,
+    m219 C219,
+    m220 C220,
+    m221 C221,
+    m222 C222,
+    m223 C223,
+    m224 C224,
+    m225 C225,
+    m226 C226,
+    m227 C227,
+    m228 C228,
+    m229 C229,
+    m230 C230,
+    m231 C231,
+    m232 C232,
+    m233 C233,
+    m234 C234,
+    m235 C235,
+    m236 C236,
+    m237 C237,
+    m238 C238,
+    m239 C239,
+    m240 C240,
+    m241 C241,
+    m242 C242,
+    m243 C243,
+    m244 C244,
+    m245 C245,
+    m246 C246,
+    m247 C247,
+    m248 C248,
+    m249 C249,
+    m250 C250,
+    m251 C251,
+    m252 C252,
+    m253 C253,
+    m254 C254,
+    m255 C255,
+    m256 C256,
+    m257 C257,
+    m258 C258,
+    m259 C259,
+    m260 C260,
+    m261 C261,
+    m262 C262,
+    m263 C263,
+    m264 C264,
+    m265 C265,
+    m266 C266,
+    m267 C267,
+    m268 C268,
+    m269 C269,
+    m270 C270,
+    m271 C271,
+    m272 C272,
+    m273 C273,
+    m274 C274,
+    m275 C275,
+    m276 C276,
+    m277 C277,
+    m278 C278,
+    m279 C279,
+    m280 C280,
+    m281 C281,
+    m282 C282,
+    m283 C283,
+    m284 C284,
+    m285 C285,
+    m286 C286,
+    m287 C287,
+    m288 C288,
+    m289 C289,
+    m290 C290,
+    m291 C291,
+    m292 C292,
+    m293 C293,
+    m294 C294,
+    m295 C295,
+    m296 C296,
+    m297 C297,
+    m298 C298,
+    m299 C299
+);